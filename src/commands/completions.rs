@@ -0,0 +1,56 @@
+use anyhow::{Context, Result};
+use clap::Command;
+use clap_complete::env::Shells;
+use clap_complete::engine::CompletionCandidate;
+use clap_complete::Shell;
+use std::io;
+
+use crate::utils::file::list_modules;
+
+/// Print the registration script for `shell`.
+/// The script calls back into axumate (`COMPLETE=<shell> axumate`), so dynamic values such as module names stay current.
+pub fn print_completions(shell: Shell) -> Result<()> {
+    let shells = Shells::builtins();
+    let completer = shells
+        .completer(&shell.to_string())
+        .with_context(|| format!("Unsupported shell: {}", shell))?;
+
+    completer.write_registration("COMPLETE", "axumate", "axumate", "axumate", &mut io::stdout())?;
+    Ok(())
+}
+
+/// Render the man page for the whole CLI to stdout.
+pub fn print_man_page(cmd: Command) -> Result<()> {
+    clap_mangen::Man::new(cmd).render(&mut io::stdout())?;
+    Ok(())
+}
+
+/// Completion candidates for existing module names, found by scanning src/.
+pub fn module_name_candidates() -> Vec<CompletionCandidate> {
+    list_modules().into_iter().map(CompletionCandidate::new).collect()
+}
+
+
+//===================================tests==========================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::testing::in_temp_project;
+    use std::fs;
+
+    #[test]
+    fn test_module_name_candidates_lists_modules_with_mod_rs() {
+        in_temp_project("completions", |root| {
+            fs::create_dir_all(root.join("src/hero")).unwrap();
+            fs::write(root.join("src/hero/mod.rs"), "").unwrap();
+            fs::create_dir_all(root.join("src/assets")).unwrap();
+
+            let names: Vec<String> = module_name_candidates()
+                .iter()
+                .map(|c| c.get_value().to_string_lossy().into_owned())
+                .collect();
+            assert_eq!(names, vec!["hero".to_string()]);
+        });
+    }
+}
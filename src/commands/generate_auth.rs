@@ -0,0 +1,226 @@
+use anyhow::{bail, Result};
+use std::fs;
+use std::path::Path;
+
+use crate::utils::field::Field;
+use crate::utils::file::*;
+use crate::utils::project::{AuthStrategy, ProjectConfig, PROJECT_FILE};
+
+use super::generate_config::generate_config_key;
+use super::generate_item::{attach_layer, insert_mod_decl, LayerOrder, MiddlewareScope};
+use super::generate_module::{nest_module, write_migration};
+use super::templates::auth_templates::*;
+use super::templates::dependencies::{auth_dependencies, ensure_dependency};
+
+/// Config keys the auth module reads for each strategy, with their defaults
+fn config_keys(strategy: AuthStrategy) -> &'static [(&'static str, &'static str, &'static str)] {
+    match strategy {
+        AuthStrategy::None => &[],
+        AuthStrategy::Jwt => &[
+            ("jwt_secret", "String", "\"change-me-in-production\""),
+            ("jwt_access_ttl_secs", "u64", "900"),
+            ("jwt_refresh_ttl_secs", "u64", "604800"),
+        ],
+        // the cookie signing key is derived from the secret, which needs at least 32 bytes
+        AuthStrategy::Session => &[
+            ("session_secret", "String", "\"change-me-in-production-at-least-32-bytes\""),
+            ("session_ttl_secs", "u64", "86400"),
+        ],
+    }
+}
+
+/// Generate the `auth` module for `strategy` and mount it under `/auth`.
+pub fn generate_auth(strategy: AuthStrategy) -> Result<()> {
+    if strategy == AuthStrategy::None {
+        bail!("Choose an auth strategy, e.g. `--strategy jwt`");
+    }
+    println!("Generating auth: {}", strategy);
+    let mut project = ProjectConfig::load()?;
+    if project.auth != AuthStrategy::None && project.auth != strategy && module_dir("auth").exists() {
+        bail!("This project already uses `{}` auth; remove src/auth/ first to switch to `{}`", project.auth, strategy);
+    }
+
+    // 1️⃣ The auth module itself
+    let auth_dir = ensure_dir(&module_dir("auth"))?;
+    let dto_dir = ensure_dir(&auth_dir.join("dto"))?;
+    let entities_dir = ensure_dir(&auth_dir.join("entities"))?;
+    fs::write(auth_dir.join("mod.rs"), auth_mod_template(strategy))?;
+    fs::write(auth_dir.join("auth_controller.rs"), auth_controller_template(strategy))?;
+    fs::write(auth_dir.join("auth_service.rs"), auth_service_template(&project, strategy))?;
+    fs::write(auth_dir.join("current_user.rs"), current_user_template(&project, strategy))?;
+    fs::write(dto_dir.join("auth_dto.rs"), auth_dto_template(&project, strategy))?;
+    fs::write(dto_dir.join("mod.rs"), "pub mod auth_dto;\n")?;
+    fs::write(entities_dir.join("user_entity.rs"), user_entity_template(&project))?;
+    let mut entities = "pub mod user_entity;\n".to_string();
+    if strategy == AuthStrategy::Session {
+        fs::write(auth_dir.join("session_store.rs"), session_store_template(&project))?;
+        fs::write(auth_dir.join("csrf.rs"), csrf_template())?;
+        fs::write(entities_dir.join("session_entity.rs"), session_entity_template(&project))?;
+        entities.push_str("pub mod session_entity;\n");
+    }
+    fs::write(entities_dir.join("mod.rs"), entities)?;
+    ensure_root_mod("auth")?;
+
+    if project.database.is_sql() {
+        write_migration("create_users", &users_migration_template(project.database))?;
+        if strategy == AuthStrategy::Session {
+            write_migration("create_sessions", &sessions_migration_template(project.database))?;
+        }
+    }
+
+    // 2️⃣ An `AuthGuard` for handlers that only need a signed-in caller
+    let guards_dir = ensure_dir(Path::new("src/guards"))?;
+    let guards_mod = ensure_mod_rs(&guards_dir)?;
+    fs::write(guards_dir.join("auth.rs"), auth_guard_template())?;
+    ensure_pub_mod_decl(&guards_mod, "auth")?;
+    ensure_root_mod("guards")?;
+
+    // 3️⃣ Secrets and lifetimes, read from config
+    let config = fs::read_to_string("src/config.rs").unwrap_or_default();
+    for (name, ty, default) in config_keys(strategy) {
+        if !config.contains(&format!("    pub {}:", name)) {
+            let key = Field { name: name.to_string(), ty: ty.to_string() };
+            generate_config_key(key, Some(default.to_string()))?;
+        }
+    }
+
+    for dep in auth_dependencies(strategy) {
+        ensure_dependency(&dep)?;
+    }
+
+    // 4️⃣ Mount the routes in main.rs
+    let main_path = Path::new("src/main.rs");
+    let mut main_rs = fs::read_to_string(main_path)?;
+    nest_module(&mut main_rs, "auth");
+    insert_mod_decl(&mut main_rs, "mod guards;");
+    if project.error_handling {
+        // the auth module rejects with `crate::error::AppError`, which the binary needs too
+        insert_mod_decl(&mut main_rs, "mod error;");
+    }
+    fs::write(main_path, main_rs)?;
+
+    if strategy == AuthStrategy::Session {
+        // the CSRF check covers every route, not only /auth
+        attach_layer("csrf", &MiddlewareScope::App, &LayerOrder::Outermost, &|_| {
+            "axum::middleware::from_fn(auth::csrf::csrf_protection)".to_string()
+        })?;
+    }
+
+    if Path::new(PROJECT_FILE).exists() {
+        project.auth = strategy;
+        project.save(Path::new("."))?;
+    }
+
+    let secret = config_keys(strategy)[0].0;
+    println!(
+        "Auth generated successfully! Set `{}` in config/production.toml or with APP_{}",
+        secret,
+        secret.to_uppercase()
+    );
+    Ok(())
+}
+
+//===================================tests==========================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::project::Database;
+    use crate::utils::testing::in_temp_project;
+
+    /// main.rs, config.rs, Cargo.toml and axumate.toml as `axumate new` leaves them, trimmed down
+    fn scaffold(root: &Path, project: ProjectConfig) {
+        fs::write(
+            root.join("src/main.rs"),
+            "use axum::Router;\n\nasync fn main() {\n    let app = Router::new()\n        .route(\"/\", get(root));\n}\n",
+        )
+        .unwrap();
+        fs::write(
+            root.join("src/config.rs"),
+            "pub struct AppConfig {\n    pub port: u16,\n}\n\nimpl Default for AppConfig {\n    fn default() -> Self {\n        AppConfig {\n            port: 8080,\n        }\n    }\n}\n",
+        )
+        .unwrap();
+        fs::write(root.join("Cargo.toml"), "[package]\nname = \"app\"\n\n[dependencies]\naxum = \"0.8.9\"\n").unwrap();
+        project.save(root).unwrap();
+    }
+
+    #[test]
+    fn test_generate_auth_creates_module_config_and_routes() {
+        in_temp_project("auth", |root| {
+            scaffold(root, ProjectConfig::default());
+
+            generate_auth(AuthStrategy::Jwt).unwrap();
+            generate_auth(AuthStrategy::Jwt).unwrap();
+
+            for file in ["mod.rs", "auth_controller.rs", "auth_service.rs", "current_user.rs", "dto/auth_dto.rs", "entities/user_entity.rs"] {
+                assert!(root.join("src/auth").join(file).exists(), "missing {}", file);
+            }
+            assert!(root.join("src/guards/auth.rs").exists());
+
+            // in memory, the service is async over a tokio lock, like the SQL one
+            let service = fs::read_to_string(root.join("src/auth/auth_service.rs")).unwrap();
+            assert!(service.contains("static USERS: Lazy<RwLock<Vec<User>>>"));
+            assert!(service.contains("pub async fn register(dto: RegisterDto) -> AuthResult<User> {"));
+            assert!(service.contains("pub async fn login(dto: LoginDto) -> AuthResult<User> {"));
+            assert!(!service.contains("std::sync::Mutex"));
+            // unknown emails cost a hash too, and a release build refuses the placeholder secret
+            assert!(service.contains("return Err(reject_unknown_email(&dto.password).await);"));
+            assert!(service.contains("let _ = verify_password(password, DUMMY_HASH).await;"));
+            assert!(service.contains("async fn hash_password(password: &str)"));
+            assert!(service.contains("async fn verify_password(password: &str, hash: &str)"));
+            assert_eq!(service.matches("tokio::task::spawn_blocking(move || {").count(), 2);
+            assert!(service.contains("verify_password(&dto.password, &user.password_hash).await?;"));
+            assert!(service.contains("if CONFIG.jwt_secret != AppConfig::default().jwt_secret {"));
+            assert!(fs::read_to_string(root.join("src/auth/mod.rs")).unwrap().contains("    auth_service::check_secret();\n"));
+            let controller = fs::read_to_string(root.join("src/auth/auth_controller.rs")).unwrap();
+            assert!(controller.contains("let user = auth_service::login(dto).await?;"));
+
+            let main_rs = fs::read_to_string(root.join("src/main.rs")).unwrap();
+            assert_eq!(main_rs.matches(".nest(\"/auth\", auth::routes())").count(), 1);
+            assert!(main_rs.contains("mod auth;") && main_rs.contains("mod guards;"));
+
+            let config = fs::read_to_string(root.join("src/config.rs")).unwrap();
+            assert_eq!(config.matches("pub jwt_secret: String,").count(), 1);
+            assert!(config.contains("jwt_access_ttl_secs: 900,"));
+
+            let manifest = fs::read_to_string(root.join("Cargo.toml")).unwrap();
+            assert!(manifest.contains("argon2 = \"0.5.3\"") && manifest.contains("jsonwebtoken = "));
+            assert_eq!(ProjectConfig::load().unwrap().auth, AuthStrategy::Jwt);
+            assert!(generate_auth(AuthStrategy::None).is_err());
+        });
+    }
+
+    #[test]
+    fn test_session_auth_adds_store_csrf_layer_and_migrations() {
+        in_temp_project("auth-session", |root| {
+            scaffold(root, ProjectConfig { database: Database::Sqlite, ..ProjectConfig::default() });
+
+            generate_auth(AuthStrategy::Session).unwrap();
+            generate_auth(AuthStrategy::Session).unwrap();
+
+            for file in ["session_store.rs", "csrf.rs", "entities/session_entity.rs"] {
+                assert!(root.join("src/auth").join(file).exists(), "missing {}", file);
+            }
+            let store = fs::read_to_string(root.join("src/auth/session_store.rs")).unwrap();
+            assert!(store.contains("INSERT INTO sessions"));
+            let service = fs::read_to_string(root.join("src/auth/auth_service.rs")).unwrap();
+            assert!(service.contains("return Err(reject_unknown_email(&dto.password).await);"));
+            assert!(service.contains("if CONFIG.session_secret != AppConfig::default().session_secret {"));
+
+            let main_rs = fs::read_to_string(root.join("src/main.rs")).unwrap();
+            assert_eq!(main_rs.matches("let app = app.layer(axum::middleware::from_fn(auth::csrf::csrf_protection));").count(), 1);
+
+            let mut migrations: Vec<String> = fs::read_dir(root.join("migrations"))
+                .unwrap()
+                .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+                .collect();
+            migrations.sort();
+            assert_eq!(migrations.len(), 2);
+            assert!(migrations[0].ends_with("_create_users.sql") && migrations[1].ends_with("_create_sessions.sql"));
+
+            let config = fs::read_to_string(root.join("src/config.rs")).unwrap();
+            assert!(config.contains("pub session_secret: String,"));
+            assert!(generate_auth(AuthStrategy::Jwt).is_err());
+        });
+    }
+}
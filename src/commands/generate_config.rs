@@ -0,0 +1,158 @@
+use anyhow::{bail, Context, Result};
+use std::fs;
+use std::path::Path;
+
+use crate::utils::field::Field;
+use crate::utils::file::ensure_dir;
+
+/// Add `key` to the generated `AppConfig`: the struct field, its default and its entry in config/default.toml.
+/// `default` is a TOML literal (`8080`, `true`, `"secret"`); without it the type's zero value is used.
+pub fn generate_config_key(key: Field, default: Option<String>) -> Result<()> {
+    println!("Generating config key: {}", key.name);
+
+    let config_path = Path::new("src/config.rs");
+    let mut content = fs::read_to_string(config_path)
+        .context("src/config.rs not found; run this inside a project created by `axumate new`")?;
+
+    let field_line = format!("    pub {}: {},", key.name, key.ty);
+    if content.contains(&format!("    pub {}:", key.name)) {
+        bail!("AppConfig already has a `{}` field", key.name);
+    }
+
+    let default_value = match &default {
+        Some(raw) => Some(parse_toml_value(raw)?),
+        None => zero_value(&key.ty),
+    };
+    let default_expr = match (&default, &default_value) {
+        (Some(_), Some(value)) => rust_literal(value)?,
+        _ => "Default::default()".to_string(),
+    };
+
+    // projects from before `#[serde(default)]` fail to load once a key is missing from every source
+    if !content.contains("#[serde(default)]\npub struct AppConfig {") {
+        let struct_pos = content.find("pub struct AppConfig {").context("`pub struct AppConfig` not found in src/config.rs")?;
+        content.insert_str(struct_pos, "#[serde(default)]\n");
+    }
+
+    // --- Insert the field at the end of `pub struct AppConfig { ... }` ---
+    let struct_pos = content.find("pub struct AppConfig {").context("`pub struct AppConfig` not found in src/config.rs")?;
+    let struct_end = struct_pos + content[struct_pos..].find("\n}").context("end of AppConfig not found")?;
+    content.insert_str(struct_end + 1, &format!("{}\n", field_line));
+
+    // --- Insert its default at the end of `AppConfig { ... }` inside `fn default()` ---
+    let default_pos = content.find("fn default()").context("`impl Default for AppConfig` not found")?;
+    let literal_pos = default_pos + content[default_pos..].find("AppConfig {").context("AppConfig literal not found")?;
+    let literal_end = literal_pos + content[literal_pos..].find("\n        }").context("end of AppConfig literal not found")?;
+    content.insert_str(literal_end, &format!("\n            {}: {},", key.name, default_expr));
+
+    fs::write(config_path, content)?;
+
+    // --- Document the key with its default in config/default.toml ---
+    if let Some(value) = default_value {
+        let toml_path = ensure_dir(Path::new("config"))?.join("default.toml");
+        let mut toml = fs::read_to_string(&toml_path).unwrap_or_default();
+        if !toml.is_empty() && !toml.ends_with('\n') {
+            toml.push('\n');
+        }
+        toml.push_str(&format!("{} = {}\n", key.name, value));
+        fs::write(&toml_path, toml)?;
+    }
+
+    println!(
+        "Config key `{}: {}` added; set it in config/*.toml or with APP_{}",
+        key.name,
+        key.ty,
+        key.name.to_uppercase()
+    );
+    Ok(())
+}
+
+/// Parse a TOML literal such as `8080`, `true` or `"secret"`.
+fn parse_toml_value(raw: &str) -> Result<toml::Value> {
+    let table: toml::Table = format!("value = {}", raw)
+        .parse()
+        .with_context(|| format!("`{}` is not a valid TOML value (quote strings: '\"text\"')", raw))?;
+    Ok(table["value"].clone())
+}
+
+/// Rust expression for a TOML default: strings get `.to_string()` and arrays become `vec![...]`.
+fn rust_literal(value: &toml::Value) -> Result<String> {
+    Ok(match value {
+        toml::Value::String(s) => format!("{:?}.to_string()", s),
+        toml::Value::Array(items) => {
+            let items = items.iter().map(rust_literal).collect::<Result<Vec<_>>>()?;
+            format!("vec![{}]", items.join(", "))
+        }
+        toml::Value::Integer(_) | toml::Value::Float(_) | toml::Value::Boolean(_) => value.to_string(),
+        toml::Value::Table(_) | toml::Value::Datetime(_) => {
+            bail!("`{}` has no Rust literal; use a scalar or an array default and set tables in config/*.toml", value)
+        }
+    })
+}
+
+/// Zero value for common types, written to config/default.toml; `None` for types without a TOML form.
+fn zero_value(ty: &str) -> Option<toml::Value> {
+    match ty {
+        "String" => Some(toml::Value::String(String::new())),
+        "bool" => Some(toml::Value::Boolean(false)),
+        "u8" | "u16" | "u32" | "u64" | "usize" | "i8" | "i16" | "i32" | "i64" | "isize" => Some(toml::Value::Integer(0)),
+        "f32" | "f64" => Some(toml::Value::Float(0.0)),
+        _ => None,
+    }
+}
+
+
+//===================================tests==========================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::templates::new_templates::{config_template, default_config_toml_template};
+    use crate::utils::project::ProjectConfig;
+    use crate::utils::testing::in_temp_project;
+
+    #[test]
+    fn test_generate_config_key_adds_field_default_and_toml() {
+        in_temp_project("config-key", |root| {
+            let project = ProjectConfig::default();
+            fs::write(root.join("src/config.rs"), config_template("app", &project)).unwrap();
+            fs::create_dir_all(root.join("config")).unwrap();
+            fs::write(root.join("config/default.toml"), default_config_toml_template("app", &project)).unwrap();
+
+            generate_config_key("jwt_secret:String".parse().unwrap(), Some("\"change-me\"".into())).unwrap();
+            generate_config_key("workers:usize".parse().unwrap(), None).unwrap();
+
+            let config = fs::read_to_string(root.join("src/config.rs")).unwrap();
+            assert!(config.contains("    pub shutdown_timeout_secs: u64,\n    pub jwt_secret: String,\n    pub workers: usize,\n}"));
+            assert!(config.contains("            jwt_secret: \"change-me\".to_string(),\n            workers: Default::default(),\n        }"));
+
+            let toml = fs::read_to_string(root.join("config/default.toml")).unwrap();
+            assert!(toml.ends_with("jwt_secret = \"change-me\"\nworkers = 0\n"));
+
+            assert!(generate_config_key("port:u16".parse().unwrap(), None).is_err());
+        });
+    }
+
+    #[test]
+    fn test_collection_keys_load_and_compile() {
+        in_temp_project("config-key-vec", |root| {
+            // a config.rs from before `#[serde(default)]`
+            let old = config_template("app", &ProjectConfig::default()).replace("#[serde(default)]\n", "");
+            fs::write(root.join("src/config.rs"), old).unwrap();
+
+            generate_config_key("tags:Vec<String>".parse().unwrap(), None).unwrap();
+            generate_config_key("hosts:Vec<String>".parse().unwrap(), Some("[\"a\", \"b\"]".into())).unwrap();
+            generate_config_key("ports:Vec<u16>".parse().unwrap(), Some("[80, 443]".into())).unwrap();
+            assert!(generate_config_key("limits:Limits".parse().unwrap(), Some("{ rps = 10 }".into())).is_err());
+
+            let config = fs::read_to_string(root.join("src/config.rs")).unwrap();
+            // missing keys (the empty `tags` is dropped when the defaults are serialized) fall back to `Default`
+            assert_eq!(config.matches("#[serde(default)]\npub struct AppConfig {").count(), 1);
+            assert!(config.contains("            tags: Default::default(),\n"));
+            assert!(config.contains("            hosts: vec![\"a\".to_string(), \"b\".to_string()],\n"));
+            assert!(config.contains("            ports: vec![80, 443],\n"));
+            syn::parse_file(&config).unwrap();
+            assert!(fs::read_to_string(root.join("config/default.toml")).unwrap().contains("hosts = [\"a\", \"b\"]\n"));
+        });
+    }
+}
@@ -0,0 +1,146 @@
+use anyhow::{bail, Context, Result};
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::utils::common::capitalize;
+use crate::utils::file::*;
+use crate::utils::project::ProjectConfig;
+
+use super::generate_item::{insert_mod_decl, merge_imports};
+use super::templates::generate_item_templates::guard_template;
+
+/// Handlers of a module's controller to guard, parsed from `module:handler,handler`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GuardTarget {
+    pub module: String,
+    pub handlers: Vec<String>,
+}
+
+impl FromStr for GuardTarget {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (module, handlers) = s
+            .split_once(':')
+            .ok_or_else(|| format!("expected `module:handler,handler`, got `{}`", s))?;
+        let handlers: Vec<String> = handlers
+            .split(',')
+            .map(str::trim)
+            .filter(|h| !h.is_empty())
+            .map(str::to_string)
+            .collect();
+        if module.trim().is_empty() || handlers.is_empty() {
+            return Err(format!("expected `module:handler,handler`, got `{}`", s));
+        }
+        Ok(GuardTarget { module: module.trim().to_string(), handlers })
+    }
+}
+
+/// Generate src/guards/{name}.rs and add the guard as a parameter of the `apply` handlers.
+pub fn generate_guard(name: String, apply: Vec<GuardTarget>) -> Result<()> {
+    println!("Generating guard: {}", name);
+    let project = ProjectConfig::load()?;
+    let guard = format!("{}Guard", capitalize(&name));
+
+    let mut controllers = Vec::new();
+    for target in &apply {
+        let path = module_dir(&target.module).join(format!("{}_controller.rs", target.module));
+        let content = fs::read_to_string(&path).with_context(|| format!("Controller of module `{}` not found", target.module))?;
+        for handler in &target.handlers {
+            if !content.contains(&format!("pub async fn {}(", handler)) {
+                bail!("Handler `{}` not found in {}", handler, path.to_string_lossy());
+            }
+        }
+        controllers.push((path, content, target));
+    }
+
+    let guards_dir = ensure_dir(Path::new("src/guards"))?;
+    let mod_rs = ensure_mod_rs(&guards_dir)?;
+    let path = guards_dir.join(format!("{}.rs", name));
+    if path.exists() {
+        println!("Guard `{}` already exists at {}", name, path.to_string_lossy());
+    } else {
+        fs::write(&path, guard_template(&name, project.error_handling))?;
+        println!("Guard {} created at {}", name, path.to_string_lossy());
+    }
+    ensure_pub_mod_decl(&mod_rs, &name)?;
+    ensure_root_mod("guards")?;
+
+    let main_path = Path::new("src/main.rs");
+    let mut main_rs = fs::read_to_string(main_path)?;
+    insert_mod_decl(&mut main_rs, "mod guards;");
+    if project.error_handling {
+        // guards reject with `crate::error::AppError`, which the binary needs too
+        insert_mod_decl(&mut main_rs, "mod error;");
+    }
+    fs::write(main_path, main_rs)?;
+
+    // --- Add `_guard: {Name}Guard` as the first parameter of each handler; routes() stays as it is ---
+    let import = format!("use crate::guards::{}::{};", name, guard);
+    for (path, mut content, target) in controllers {
+        merge_imports(&mut content, &[import.as_str()]);
+        for handler in &target.handlers {
+            add_guard_param(&mut content, handler, &guard);
+        }
+        fs::write(&path, content)?;
+        println!("Guarded {}: {}", target.module, target.handlers.join(", "));
+    }
+
+    println!("Guard `{}` generated successfully!", name);
+    Ok(())
+}
+
+/// Make `guard` the first parameter of `handler`, unless it already takes it.
+fn add_guard_param(content: &mut String, handler: &str, guard: &str) {
+    let Some(pos) = content.find(&format!("pub async fn {}(", handler)) else {
+        return;
+    };
+    let open = pos + content[pos..].find('(').unwrap_or_default() + 1;
+    let params = &content[open..open + content[open..].find(')').unwrap_or_default()];
+    if params.contains(guard) {
+        return;
+    }
+    let param = if params.trim().is_empty() {
+        format!("_guard: {}", guard)
+    } else {
+        format!("_guard: {}, ", guard)
+    };
+    content.insert_str(open, &param);
+}
+
+
+//===================================tests==========================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::testing::in_temp_project;
+
+    #[test]
+    fn test_guard_is_added_to_selected_handlers() {
+        in_temp_project("guard", |root| {
+            fs::write(root.join("src/main.rs"), "use axum::Router;\n\nasync fn main() {}\n").unwrap();
+            fs::create_dir_all(root.join("src/hero")).unwrap();
+            fs::write(
+                root.join("src/hero/hero_controller.rs"),
+                "use axum::Json;\n\npub async fn find_all() -> String {}\n\npub async fn remove(Path(id): Path<u32>) -> String {}\n",
+            )
+            .unwrap();
+
+            let target: GuardTarget = "hero:find_all,remove".parse().unwrap();
+            generate_guard("admin".into(), vec![target.clone()]).unwrap();
+            generate_guard("admin".into(), vec![target]).unwrap();
+
+            let controller = fs::read_to_string(root.join("src/hero/hero_controller.rs")).unwrap();
+            assert!(controller.starts_with("use axum::Json;\nuse crate::guards::admin::AdminGuard;\n"));
+            assert!(controller.contains("pub async fn find_all(_guard: AdminGuard) -> String"));
+            assert!(controller.contains("pub async fn remove(_guard: AdminGuard, Path(id): Path<u32>) -> String"));
+            assert!(root.join("src/guards/admin.rs").exists());
+
+            let unknown = generate_guard("admin".into(), vec!["hero:nope".parse().unwrap()]);
+            assert!(unknown.is_err());
+            assert!("hero".parse::<GuardTarget>().is_err());
+        });
+    }
+}
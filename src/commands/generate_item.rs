@@ -1,7 +1,10 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
+use clap::ValueEnum;
 use std::fs;
-use std::io::{Read, Write};
-use std::path::{Path, PathBuf};
+use std::io::Read;
+use std::path::Path;
+use crate::utils::common::capitalize;
+use crate::utils::field::Field;
 use crate::utils::file::*;
 
 use super::templates::generate_item_templates::{
@@ -10,20 +13,53 @@ use super::templates::generate_item_templates::{
     simple_entity_template,
     simple_service_template,
     middleware_function_template,
-    middleware_dependencies_template
+    middleware_dependencies_template,
+    request_id_middleware_template,
+    request_id_support_template,
+    timing_middleware_template,
+    auth_header_middleware_template,
+    security_headers_middleware_template,
+    rate_limit_middleware_template,
+    cors_layer_template,
+    compression_layer_template,
+    timeout_layer_template,
+    body_limit_layer_template,
+    layer_template,
+    api_key_support_template,
+    api_key_middleware_template,
+    api_key_config_template,
+    api_keys_toml_template,
+    API_KEY_MARKER,
+    REQUEST_ID_MARKER,
 };
+use super::generate_config::generate_config_key;
+use super::templates::dependencies::{ensure_dependency, id_dependencies, sha2, timestamp_dependencies, tower, tower_http};
+use super::templates::new_templates::state_template;
+use crate::utils::project::{IdStrategy, ProjectConfig};
+
+/// Id type of a standalone item: `id` when given, else the project's, with the crates it needs added to Cargo.toml
+fn item_id(id: Option<IdStrategy>) -> Result<IdStrategy> {
+    let project = ProjectConfig::load()?;
+    let id = id.unwrap_or(project.id_strategy());
+    id.check(project.database)?;
+    for dep in id_dependencies(id, &project) {
+        ensure_dependency(&dep)?;
+    }
+    Ok(id)
+}
 
 /// Generate a standalone controller in {cwd}/{name}/{name}_controller.rs
-pub fn generate_controller(name: String) -> Result<()> {
+pub fn generate_controller(name: String, id: Option<IdStrategy>) -> Result<()> {
     println!("Generating simple controller: {}", name);
+    let id = item_id(id)?;
 
     // ensure target module dir
-    let mdir = ensure_dir(&Path::new(&name))?;
+    let mdir = ensure_dir(Path::new(&name))?;
     let mod_rs = ensure_mod_rs(&mdir)?;
 
     // file path
     let filename = mdir.join(format!("{}_controller.rs", name));
-    fs::write(&filename, simple_controller_template(&name))?;
+    fs::write(&filename, simple_controller_template(&name, id.rust_type()))?;
 
     // declare controller in that module's mod.rs
     ensure_pub_mod_decl(&mod_rs, &format!("{}_controller", name))?;
@@ -37,14 +73,15 @@ pub fn generate_controller(name: String) -> Result<()> {
 }
 
 /// Generate a standalone service in {cwd}/{name}/{name}_service.rs
-pub fn generate_service(name: String) -> Result<()> {
+pub fn generate_service(name: String, id: Option<IdStrategy>) -> Result<()> {
     println!("Generating simple service: {}", name);
+    let id = item_id(id)?;
 
-    let mdir = ensure_dir(&Path::new(&name))?;
+    let mdir = ensure_dir(Path::new(&name))?;
     let mod_rs = ensure_mod_rs(&mdir)?;
 
     let filename = mdir.join(format!("{}_service.rs", name));
-    fs::write(&filename, simple_service_template(&name))?;
+    fs::write(&filename, simple_service_template(&name, id.rust_type()))?;
 
     ensure_pub_mod_decl(&mod_rs, &format!("{}_service", name))?;
 
@@ -56,14 +93,14 @@ pub fn generate_service(name: String) -> Result<()> {
 }
 
 /// Generate a standalone DTO in {cwd}/{name}/{name}_dto.rs
-pub fn generate_dto(name: String) -> Result<()> {
+pub fn generate_dto(name: String, fields: Vec<Field>) -> Result<()> {
     println!("Generating simple dto: {}", name);
 
-    let mdir = ensure_dir(&Path::new(&name))?;
+    let mdir = ensure_dir(Path::new(&name))?;
     let mod_rs = ensure_mod_rs(&mdir)?;
 
     let filename = mdir.join(format!("{}_dto.rs", name));
-    fs::write(&filename, simple_dto_template(&name))?;
+    fs::write(&filename, simple_dto_template(&name, &fields))?;
 
     ensure_pub_mod_decl(&mod_rs, &format!("{}_dto", name))?;
 
@@ -75,14 +112,20 @@ pub fn generate_dto(name: String) -> Result<()> {
 }
 
 /// Generate a standalone entity in {cwd}/{name}/{name}_entity.rs
-pub fn generate_entity(name: String) -> Result<()> {
+pub fn generate_entity(name: String, fields: Vec<Field>, id: Option<IdStrategy>, timestamps: bool, soft_delete: bool) -> Result<()> {
     println!("Generating simple entity: {}", name);
+    let id = item_id(id)?;
+    if timestamps || soft_delete {
+        for dep in timestamp_dependencies(&ProjectConfig::load()?) {
+            ensure_dependency(&dep)?;
+        }
+    }
 
-    let mdir = ensure_dir(&Path::new(&name))?;
+    let mdir = ensure_dir(Path::new(&name))?;
     let mod_rs = ensure_mod_rs(&mdir)?;
 
     let filename = mdir.join(format!("{}_entity.rs", name));
-    fs::write(&filename, simple_entity_template(&name))?;
+    fs::write(&filename, simple_entity_template(&name, &fields, id.rust_type(), timestamps, soft_delete))?;
 
     ensure_pub_mod_decl(&mod_rs, &format!("{}_entity", name))?;
 
@@ -93,65 +136,661 @@ pub fn generate_entity(name: String) -> Result<()> {
     Ok(())
 }
 
-pub fn generate_middleware(name: String) -> anyhow::Result<()> {
+/// Built-in shapes `generate middleware` can produce
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum MiddlewareKind {
+    /// Log method, path and status of every request
+    #[default]
+    Logger,
+    /// Assign or propagate `x-request-id`, record it in spans and expose it through the `RequestId` extractor
+    RequestId,
+    /// Add an `x-response-time-ms` header and log the duration
+    Timing,
+    /// Reject requests without an `Authorization: Bearer` header
+    AuthHeader,
+    /// tower-http CORS layer
+    Cors,
+    /// tower-http gzip compression layer
+    Compression,
+    /// tower-http timeout layer (408 after 30 seconds)
+    Timeout,
+    /// tower-http request body limit layer (413 over 2 MiB)
+    BodyLimit,
+    /// Set nosniff, frame, referrer and HSTS headers
+    SecurityHeaders,
+    /// Allow a fixed number of requests per second across all clients
+    RateLimit,
+    /// Require an `x-api-key` configured (as a SHA-256 digest) with this middleware's name among its scopes
+    ApiKey,
+}
+
+impl MiddlewareKind {
+    /// Kinds emitted as a configured tower-http layer (`{name}_layer()`) rather than a `from_fn` function
+    fn tower_http_feature(self) -> Option<&'static str> {
+        match self {
+            MiddlewareKind::Cors => Some("cors"),
+            MiddlewareKind::Compression => Some("compression-gzip"),
+            MiddlewareKind::Timeout => Some("timeout"),
+            MiddlewareKind::BodyLimit => Some("limit"),
+            _ => None,
+        }
+    }
+
+    /// `use` lines the generated code needs in middlewares.rs, one item each so they can be merged
+    fn imports(self, tracing: bool) -> Vec<&'static str> {
+        match self {
+            MiddlewareKind::Logger if tracing => vec!["use tracing::Instrument;"],
+            MiddlewareKind::Logger => vec![],
+            MiddlewareKind::RequestId => {
+                let mut imports = vec![
+                    "use axum::extract::FromRequestParts;",
+                    "use axum::http::request::Parts;",
+                    "use axum::http::HeaderValue;",
+                    "use axum::http::StatusCode;",
+                    "use std::sync::atomic::AtomicU64;",
+                    "use std::sync::atomic::Ordering;",
+                    "use std::time::SystemTime;",
+                    "use std::time::UNIX_EPOCH;",
+                ];
+                if tracing {
+                    imports.push("use tracing::Instrument;");
+                }
+                imports
+            }
+            MiddlewareKind::Timing => vec!["use axum::http::HeaderValue;", "use std::time::Instant;"],
+            MiddlewareKind::AuthHeader => vec![
+                "use axum::http::header;",
+                "use axum::http::StatusCode;",
+                "use axum::response::IntoResponse;",
+            ],
+            MiddlewareKind::SecurityHeaders => vec!["use axum::http::HeaderValue;"],
+            MiddlewareKind::RateLimit => vec![
+                "use axum::http::StatusCode;",
+                "use axum::response::IntoResponse;",
+                "use once_cell::sync::Lazy;",
+                "use std::sync::Mutex;",
+                "use std::time::Duration;",
+                "use std::time::Instant;",
+            ],
+            MiddlewareKind::ApiKey => vec![
+                "use axum::http::StatusCode;",
+                "use axum::response::IntoResponse;",
+                "use sha2::Digest;",
+                "use sha2::Sha256;",
+                "use crate::config::ApiKey;",
+                "use crate::config::CONFIG;",
+            ],
+            MiddlewareKind::Cors => vec!["use tower_http::cors::Any;", "use tower_http::cors::CorsLayer;"],
+            MiddlewareKind::Compression => vec!["use tower_http::compression::CompressionLayer;"],
+            MiddlewareKind::Timeout => vec![
+                "use axum::http::StatusCode;",
+                "use std::time::Duration;",
+                "use tower_http::timeout::TimeoutLayer;",
+            ],
+            MiddlewareKind::BodyLimit => vec!["use tower_http::limit::RequestBodyLimitLayer;"],
+        }
+    }
+
+    /// Function (plus any shared items missing from `existing`) to append to middlewares.rs
+    fn code(self, name: &str, tracing: bool, existing: &str) -> String {
+        match self {
+            MiddlewareKind::Logger => middleware_function_template(name, tracing),
+            MiddlewareKind::RequestId => {
+                let mut code = String::new();
+                if !existing.contains(REQUEST_ID_MARKER) {
+                    code.push_str(&request_id_support_template());
+                }
+                code.push_str(&request_id_middleware_template(name, tracing));
+                code
+            }
+            MiddlewareKind::Timing => timing_middleware_template(name, tracing),
+            MiddlewareKind::AuthHeader => auth_header_middleware_template(name),
+            MiddlewareKind::SecurityHeaders => security_headers_middleware_template(name),
+            MiddlewareKind::RateLimit => rate_limit_middleware_template(name),
+            MiddlewareKind::ApiKey => {
+                let mut code = String::new();
+                if !existing.contains(API_KEY_MARKER) {
+                    code.push_str(&api_key_support_template());
+                }
+                code.push_str(&api_key_middleware_template(name));
+                code
+            }
+            MiddlewareKind::Cors => cors_layer_template(name),
+            MiddlewareKind::Compression => compression_layer_template(name),
+            MiddlewareKind::Timeout => timeout_layer_template(name),
+            MiddlewareKind::BodyLimit => body_limit_layer_template(name),
+        }
+    }
+
+    /// Name of the generated item, used to tell whether it already exists
+    fn item(self, name: &str) -> String {
+        match self.tower_http_feature() {
+            Some(_) => format!("pub fn {}_layer()", name),
+            None => format!("pub async fn {}_middleware", name),
+        }
+    }
+
+    /// Argument of `.layer(...)`; `root` is `crate::` outside main.rs
+    fn layer_expr(self, name: &str, root: &str, stateful: bool) -> String {
+        match (self.tower_http_feature(), root, stateful) {
+            (Some(_), _, _) => format!("{}middlewares::{}_layer()", root, name),
+            (None, "", false) => format!("middleware::from_fn(middlewares::{}_middleware)", name),
+            (None, _, false) => format!("axum::middleware::from_fn({}middlewares::{}_middleware)", root, name),
+            // only app-wide middlewares are stateful: they take the `state` main.rs builds
+            (None, _, true) => format!("middleware::from_fn_with_state(state.clone(), middlewares::{}_middleware)", name),
+        }
+    }
+}
+
+/// Add each missing `use` line after the last one already in `content`.
+pub fn merge_imports(content: &mut String, imports: &[&str]) {
+    for import in imports {
+        if content.lines().any(|line| line.trim() == *import) {
+            continue;
+        }
+        let insert_pos = content
+            .rfind("\nuse ")
+            .map(|pos| pos + 1)
+            .or_else(|| content.starts_with("use ").then_some(0))
+            .and_then(|pos| content[pos..].find('\n').map(|end| pos + end + 1))
+            .unwrap_or(0);
+        content.insert_str(insert_pos, &format!("{}\n", import));
+    }
+}
+
+/// Where `generate middleware` attaches the layer
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MiddlewareScope {
+    /// Around the whole app in main.rs
+    App,
+    /// Around every route of a module's `routes()`
+    Module(String),
+    /// Around one route, e.g. `/hero/{id}`
+    Route(String),
+}
+
+/// Position of the new layer relative to the others in the same scope
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LayerOrder {
+    /// Wrap everything already attached, so it runs first
+    Outermost,
+    /// Run before the named middleware
+    Before(String),
+    /// Run after the named middleware
+    After(String),
+}
+
+/// Everything `generate middleware` was asked for
+pub struct MiddlewareOptions {
+    pub name: String,
+    pub kind: MiddlewareKind,
+    pub scope: MiddlewareScope,
+    pub order: LayerOrder,
+    /// Take `State<AppState>` and attach with `from_fn_with_state`
+    pub stateful: bool,
+}
+
+pub fn generate_middleware(options: MiddlewareOptions) -> anyhow::Result<()> {
+    let MiddlewareOptions { ref name, kind, stateful, .. } = options;
     println!("Generating middleware: {}", name);
+    let project = ProjectConfig::load()?;
+    if stateful && kind.tower_http_feature().is_some() {
+        anyhow::bail!("`--stateful` needs a `from_fn` kind; configure tower-http layers in their `{}_layer()` function", name);
+    }
+    // a routes() function has no state in scope, and a fresh `AppState::new()` there would share nothing with main.rs's
+    if stateful && options.scope != MiddlewareScope::App {
+        anyhow::bail!(
+            "`--stateful` middlewares are attached app-wide, where main.rs builds the one `AppState`; drop `--module`/`--route`"
+        );
+    }
+    if kind == MiddlewareKind::ApiKey && !Path::new("src/config.rs").exists() {
+        anyhow::bail!("api-key middlewares read their keys from src/config.rs; run this inside a project created by `axumate new`");
+    }
+
+    attach_layer(name, &options.scope, &options.order, &|root| kind.layer_expr(name, root, stateful))?;
 
     // --- Step 1: Ensure `src/middlewares.rs` exists and has the function ---
     let middlewares_path = Path::new("src/middlewares.rs");
 
-    if middlewares_path.exists() {
-        let mut content = fs::read_to_string(&middlewares_path)?;
-        let func_identifier = format!("pub async fn {}_middleware", name);
-        if !content.contains(&func_identifier) {
-            content.push_str(&middleware_function_template(&name));
-            fs::write(&middlewares_path, content)?;
-            println!("Added `{}` to middlewares.rs", func_identifier);
-        } else {
-            println!("Middleware `{}` already exists in middlewares.rs", name);
+    let mut content = if middlewares_path.exists() {
+        fs::read_to_string(middlewares_path)?
+    } else {
+        middleware_dependencies_template()
+    };
+    let item = kind.item(name);
+    if !content.contains(&item) {
+        let mut imports = kind.imports(project.tracing);
+        if stateful {
+            imports.extend(["use axum::extract::State;", "use crate::state::AppState;"]);
+        }
+        merge_imports(&mut content, &imports);
+        let mut code = kind.code(name, project.tracing, &content);
+        if stateful {
+            code = code.replacen(
+                &format!("pub async fn {}_middleware(", name),
+                &format!(
+                    "/// Shared settings and clients come in through `AppState` (src/state.rs)\npub async fn {}_middleware(State(_state): State<AppState>, ",
+                    name
+                ),
+                1,
+            );
         }
+        content.push_str(&code);
+        fs::write(middlewares_path, content)?;
+        println!("Added `{}` to middlewares.rs", item);
     } else {
-        let mut content = String::new();
-        content.push_str(&middleware_dependencies_template());
-        content.push_str("\n");
-        content.push_str(&middleware_function_template(&name));
-        fs::write(&middlewares_path, content)?;
-        println!("Created middlewares.rs with `{}`", name);
+        println!("Middleware `{}` already exists in middlewares.rs", name);
+    }
+
+    // layers come from tower-http, with one feature per kind
+    if let Some(feature) = kind.tower_http_feature() {
+        ensure_dependency(&tower_http(&[feature]))?;
+    }
+    if kind == MiddlewareKind::ApiKey {
+        ensure_api_keys_config()?;
+        ensure_dependency(&sha2())?;
     }
 
+    // handlers in lib modules reach extractors such as `RequestId` through `crate::middlewares`
+    ensure_root_mod("middlewares")?;
+
     // --- Step 2: Update main.rs ---
     let main_path = Path::new("src/main.rs");
     let mut content = String::new();
-    fs::File::open(&main_path)?.read_to_string(&mut content)?;
-
-    // Insert `mod middlewares;` if missing
-    let mod_decl = "mod middlewares;";
-    if !content.contains(mod_decl) {
-        if let Some(pos) = content.rfind("use ") {
-            if let Some(end) = content[pos..].find(';') {
-                let insert_pos = pos + end + 1;
-                content.insert_str(insert_pos, &format!("\n{}", mod_decl));
+    fs::File::open(main_path)?.read_to_string(&mut content)?;
+
+    insert_mod_decl(&mut content, "mod middlewares;");
+
+    if stateful {
+        let state_path = Path::new("src/state.rs");
+        if !state_path.exists() {
+            fs::write(state_path, state_template(project.database))?;
+            println!("Created src/state.rs with `AppState`");
+        }
+        ensure_root_mod("state")?;
+        insert_mod_decl(&mut content, "mod state;");
+
+        // app-wide stateful layers take `state.clone()`, built once right before the router
+        let state_stmt = "    let state = state::AppState::new();";
+        if options.scope == MiddlewareScope::App
+            && !content.contains(state_stmt)
+            && let Some(pos) = content.find("let app = Router::new()")
+            && let Some(mut line_start) = content[..pos].rfind('\n')
+        {
+            // ahead of the router and the comment lines right above it
+            while let Some(prev) = content[..line_start].rfind('\n')
+                && content[prev + 1..line_start].trim_start().starts_with("//")
+            {
+                line_start = prev;
             }
-        } else {
-            content = format!("{}\n{}", mod_decl, content);
+            content.insert_str(line_start, &format!("\n    // State shared with stateful middlewares\n{}\n", state_stmt));
         }
     }
 
-    // Insert `.layer(...)` **right after `Router::new()`**
-    let layer_line = format!(
-        "    .layer(middleware::from_fn(middlewares::{}_middleware))",
-        name
-    );
-    if !content.contains(&layer_line) {
-        if let Some(pos) = content.find("Router::new()") {
-            let insert_pos = pos + "Router::new()".len();
-            content.insert_str(insert_pos, &format!("\n{}", layer_line));
+    fs::write(main_path, content)?;
+
+    println!("Middleware `{}` generated successfully!", name);
+
+    Ok(())
+}
+
+/// Add the `api_keys` setting and its `ApiKey` type to src/config.rs, with a commented example in config/default.toml.
+fn ensure_api_keys_config() -> Result<()> {
+    let config_path = Path::new("src/config.rs");
+    if fs::read_to_string(config_path)?.contains("    pub api_keys:") {
+        return Ok(());
+    }
+    generate_config_key(Field::new("api_keys", "Vec<ApiKey>"), None)?;
+    let mut config = fs::read_to_string(config_path)?;
+    config.push_str(&api_key_config_template());
+    fs::write(config_path, config)?;
+
+    let toml_path = ensure_dir(Path::new("config"))?.join("default.toml");
+    let mut toml = fs::read_to_string(&toml_path).unwrap_or_default();
+    toml.push_str(&api_keys_toml_template());
+    fs::write(&toml_path, toml)?;
+    Ok(())
+}
+
+/// Generate a tower `Layer`/`Service` pair in src/layers/{name}.rs and attach it like a middleware.
+pub fn generate_layer(name: String, scope: MiddlewareScope, order: LayerOrder) -> Result<()> {
+    println!("Generating layer: {}", name);
+    let cap = capitalize(&name);
+
+    attach_layer(&name, &scope, &order, &|root| {
+        format!("{0}layers::{1}::{2}Layer::new({0}layers::{1}::{2}Config::default())", root, name, cap)
+    })?;
+
+    let layers_dir = ensure_dir(Path::new("src/layers"))?;
+    let mod_rs = ensure_mod_rs(&layers_dir)?;
+    let path = layers_dir.join(format!("{}.rs", name));
+    if path.exists() {
+        println!("Layer `{}` already exists at {}", name, path.to_string_lossy());
+    } else {
+        fs::write(&path, layer_template(&name))?;
+        println!("Layer {} created at {}", name, path.to_string_lossy());
+    }
+    ensure_pub_mod_decl(&mod_rs, &name)?;
+    ensure_root_mod("layers")?;
+    ensure_dependency(&tower())?;
+
+    let main_path = Path::new("src/main.rs");
+    let mut content = fs::read_to_string(main_path)?;
+    insert_mod_decl(&mut content, "mod layers;");
+    fs::write(main_path, content)?;
+
+    println!("Layer `{}` generated successfully!", name);
+    Ok(())
+}
+
+/// Insert `decl` after the last `use ...;` of main.rs, if missing.
+pub fn insert_mod_decl(content: &mut String, decl: &str) {
+    if content.contains(decl) {
+        return;
+    }
+    if let Some(pos) = content.rfind("use ") {
+        if let Some(end) = content[pos..].find(';') {
+            let insert_pos = pos + end + 1;
+            content.insert_str(insert_pos, &format!("\n{}", decl));
         }
+    } else {
+        *content = format!("{}\n{}", decl, content);
     }
+}
 
-    fs::write(&main_path, content)?;
+/// Add a layer to its scope: main.rs, a module's `routes()` or a single route.
+/// Call it before writing anything else: an unknown module, route or `--before`/`--after` target
+/// fails here and leaves the project untouched.
+/// `layer_expr` renders the `.layer(...)` argument given the path root (`""` in main.rs, `crate::` elsewhere).
+pub fn attach_layer(name: &str, scope: &MiddlewareScope, order: &LayerOrder, layer_expr: &dyn Fn(&str) -> String) -> Result<()> {
+    let main_path = Path::new("src/main.rs");
+    let (path, region, outermost, item) = match scope {
+        MiddlewareScope::App => {
+            // every statement from the router onwards; the layer wraps the finished app
+            let content = fs::read_to_string(main_path)?;
+            let start = content.find("let app = ").context("No `let app = ...` found in main.rs")?;
+            let last = content.rfind("let app = ").unwrap_or(start);
+            let end = last + content[last..].find(';').context("Unterminated `let app` statement in main.rs")? + 1;
+            (main_path.to_path_buf(), start..end, end, format!("    let app = app.layer({});", layer_expr("")))
+        }
+        MiddlewareScope::Module(module) => {
+            // the end of the Router chain returned by the module's routes()
+            let mod_rs = module_dir(module).join("mod.rs");
+            let content = fs::read_to_string(&mod_rs).with_context(|| format!("Module `{}` not found", module))?;
+            let start = content
+                .find("pub fn routes() -> Router {")
+                .with_context(|| format!("Module `{}` has no routes()", module))?;
+            let end = start + content[start..].find("\n}").context("Unterminated routes() function")?;
+            (mod_rs, start..end, end, format!("        .layer({})", layer_expr("crate::")))
+        }
+        MiddlewareScope::Route(route) => {
+            // the method router of that single `.route(...)` call
+            let (path, local) = route_file(route)?;
+            let content = fs::read_to_string(&path)?;
+            let start = content
+                .find(&format!(".route(\"{}\",", local))
+                .with_context(|| format!("No route `{}` found", route))?;
+            let end = matching_paren(&content, start + ".route".len()).context("Unterminated `.route(` call")?;
+            (path, start..end, end, format!("            .route_layer({})", layer_expr("crate::")))
+        }
+    };
 
-    println!("main.rs updated with middleware `{}` at the top of Router::new()", name);
-    println!("Middleware `{}` generated successfully!", name);
+    let mut content = fs::read_to_string(&path)?;
+    if content[region.clone()].contains(&item) {
+        println!("`{}` is already attached there", name);
+        return Ok(());
+    }
 
+    let insert_pos = match order {
+        LayerOrder::Outermost => outermost,
+        LayerOrder::Before(other) | LayerOrder::After(other) => {
+            let (other_start, other_end) = find_layer(&content, region, other)
+                .with_context(|| format!("`{}` is not attached there", other))?;
+            // later layers wrap earlier ones, so running first means coming after in the source
+            if matches!(order, LayerOrder::Before(_)) { other_end } else { other_start }
+        }
+    };
+    content.insert_str(insert_pos, &format!("\n{}", item));
+    fs::write(&path, content)?;
+
+    println!("{} updated with `{}`", path.to_string_lossy(), name);
     Ok(())
 }
+
+/// File declaring `route` and the path it is declared with there:
+/// a module's routes() for paths under a nested module, main.rs otherwise.
+fn route_file(route: &str) -> Result<(std::path::PathBuf, String)> {
+    let trimmed = route.trim_start_matches('/');
+    let (first, rest) = trimmed.split_once('/').unwrap_or((trimmed, ""));
+    let mod_rs = module_dir(first).join("mod.rs");
+    if !first.is_empty() && mod_rs.exists() {
+        return Ok((mod_rs, format!("/{}", rest)));
+    }
+    Ok((Path::new("src/main.rs").to_path_buf(), route.to_string()))
+}
+
+/// Index just past the `)` closing the `(` at `open`.
+fn matching_paren(content: &str, open: usize) -> Option<usize> {
+    let mut depth = 0;
+    for (i, c) in content[open..].char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(open + i);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Span of another middleware's layer item within `region`: from the end of the
+/// previous item (the newline before it) to the end of its own `.layer(...)`/statement.
+fn find_layer(content: &str, region: std::ops::Range<usize>, other: &str) -> Option<(usize, usize)> {
+    let scoped = &content[region.clone()];
+    let found = [
+        format!("middlewares::{}_middleware)", other),
+        format!("middlewares::{}_layer()", other),
+        format!("layers::{}::", other),
+    ]
+        .iter()
+        .find_map(|needle| scoped.find(needle.as_str()))?;
+    let line_start = region.start + scoped[..found].rfind('\n')?;
+    let open = line_start + content[line_start..].find('(')?;
+    let mut end = matching_paren(content, open)? + 1;
+    if content[end..].starts_with(';') {
+        end += 1;
+    }
+    Some((line_start, end))
+}
+
+
+//===================================tests==========================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::templates::new_templates::config_template;
+    use crate::utils::testing::in_temp_project;
+
+    fn options(name: &str, kind: MiddlewareKind, scope: MiddlewareScope, order: LayerOrder) -> MiddlewareOptions {
+        MiddlewareOptions { name: name.to_string(), kind, scope, order, stateful: false }
+    }
+
+    #[test]
+    fn test_request_id_middleware_wraps_the_whole_app() {
+        in_temp_project("middleware-request-id", |root| {
+            fs::write(
+                root.join("src/main.rs"),
+                "use axum::Router;\n\nasync fn main() {\n    let app = Router::new();\n\n    serve(app);\n}\n",
+            )
+            .unwrap();
+            generate_middleware(options("request_id", MiddlewareKind::RequestId, MiddlewareScope::App, LayerOrder::Outermost)).unwrap();
+            generate_middleware(options("correlation", MiddlewareKind::RequestId, MiddlewareScope::App, LayerOrder::Outermost)).unwrap();
+
+            let middlewares = fs::read_to_string(root.join("src/middlewares.rs")).unwrap();
+            assert_eq!(middlewares.matches(REQUEST_ID_MARKER).count(), 1);
+            assert!(middlewares.contains("pub async fn correlation_middleware(mut req: Request"));
+            let main_rs = fs::read_to_string(root.join("src/main.rs")).unwrap();
+            assert!(main_rs.contains(
+                "let app = Router::new();\n    let app = app.layer(middleware::from_fn(middlewares::request_id_middleware));"
+            ));
+            assert!(fs::read_to_string(root.join("src/lib.rs")).unwrap().contains("pub mod middlewares;"));
+        });
+    }
+
+    #[test]
+    fn test_layer_kinds_merge_imports_and_wrap_the_app() {
+        in_temp_project("middleware-layers", |root| {
+            fs::write(root.join("src/main.rs"), "use axum::Router;\n\nasync fn main() {\n    let app = Router::new();\n}\n").unwrap();
+            generate_middleware(options("cors", MiddlewareKind::Cors, MiddlewareScope::App, LayerOrder::Outermost)).unwrap();
+            generate_middleware(options("limit", MiddlewareKind::RateLimit, MiddlewareScope::App, LayerOrder::Outermost)).unwrap();
+            generate_middleware(options("timeout", MiddlewareKind::Timeout, MiddlewareScope::App, LayerOrder::Outermost)).unwrap();
+
+            let middlewares = fs::read_to_string(root.join("src/middlewares.rs")).unwrap();
+            assert_eq!(middlewares.matches("use std::time::Duration;").count(), 1);
+            assert!(middlewares.starts_with("use axum::extract::Request;"));
+            assert!(middlewares.contains("use tower_http::cors::CorsLayer;\n"));
+            assert!(middlewares.contains("pub fn cors_layer() -> CorsLayer {"));
+            let main_rs = fs::read_to_string(root.join("src/main.rs")).unwrap();
+            assert!(main_rs.contains(
+                "    let app = app.layer(middlewares::cors_layer());\n    let app = app.layer(middleware::from_fn(middlewares::limit_middleware));"
+            ));
+        });
+    }
+
+    #[test]
+    fn test_module_and_route_scopes_respect_ordering() {
+        in_temp_project("middleware-scopes", |root| {
+            fs::write(root.join("src/main.rs"), "use axum::Router;\nmod hero;\n\nasync fn main() {\n    let app = Router::new();\n}\n").unwrap();
+            fs::create_dir_all(root.join("src/hero")).unwrap();
+            fs::write(
+                root.join("src/hero/mod.rs"),
+                "pub fn routes() -> Router {\n    Router::new()\n        .route(\"/\", get(list))\n        .route(\"/{id}\", get(one)\n            .delete(remove))\n}\n",
+            )
+            .unwrap();
+            let module = || MiddlewareScope::Module("hero".into());
+            generate_middleware(options("auth", MiddlewareKind::AuthHeader, module(), LayerOrder::Outermost)).unwrap();
+            generate_middleware(options("timing", MiddlewareKind::Timing, module(), LayerOrder::After("auth".into()))).unwrap();
+            let route = MiddlewareScope::Route("/hero/{id}".into());
+            generate_middleware(options("slow", MiddlewareKind::Timeout, route, LayerOrder::Outermost)).unwrap();
+
+            let mod_rs = fs::read_to_string(root.join("src/hero/mod.rs")).unwrap();
+            assert!(mod_rs.ends_with(
+                "            .delete(remove)
+            .route_layer(crate::middlewares::slow_layer()))
+        .layer(axum::middleware::from_fn(crate::middlewares::timing_middleware))
+        .layer(axum::middleware::from_fn(crate::middlewares::auth_middleware))
+}
+"
+            ));
+            let missing = generate_middleware(options("x", MiddlewareKind::Logger, module(), LayerOrder::Before("nope".into())));
+            assert!(missing.is_err());
+            assert!(!fs::read_to_string(root.join("src/middlewares.rs")).unwrap().contains("pub async fn x_middleware"));
+        });
+    }
+
+    #[test]
+    fn test_stateful_middleware_gets_app_state() {
+        in_temp_project("middleware-stateful", |root| {
+            fs::write(
+                root.join("src/main.rs"),
+                "use axum::Router;\n\nasync fn main() {\n    // Compose the routes\n    let app = Router::new();\n}\n",
+            )
+            .unwrap();
+            let stateful = |name: &str, kind| MiddlewareOptions { stateful: true, ..options(name, kind, MiddlewareScope::App, LayerOrder::Outermost) };
+            generate_middleware(stateful("keys", MiddlewareKind::Logger)).unwrap();
+            assert!(generate_middleware(stateful("cors", MiddlewareKind::Cors)).is_err());
+
+            // a module or route has no `state` to share, so scoped stateful middlewares are refused untouched
+            fs::create_dir_all(root.join("src/hero")).unwrap();
+            fs::write(root.join("src/hero/mod.rs"), "pub fn routes() -> Router {\n    Router::new()\n}\n").unwrap();
+            let scoped = MiddlewareOptions { stateful: true, ..options("audit", MiddlewareKind::Logger, MiddlewareScope::Module("hero".into()), LayerOrder::Outermost) };
+            assert!(generate_middleware(scoped).unwrap_err().to_string().contains("drop `--module`/`--route`"));
+            assert_eq!(fs::read_to_string(root.join("src/hero/mod.rs")).unwrap(), "pub fn routes() -> Router {\n    Router::new()\n}\n");
+
+            let middlewares = fs::read_to_string(root.join("src/middlewares.rs")).unwrap();
+            assert!(middlewares.contains("pub async fn keys_middleware(State(_state): State<AppState>, req: Request, next: Next)"));
+            assert!(middlewares.contains("use crate::state::AppState;\n"));
+            assert!(!middlewares.contains("audit_middleware"));
+            assert!(root.join("src/state.rs").exists());
+            let main_rs = fs::read_to_string(root.join("src/main.rs")).unwrap();
+            assert!(main_rs.contains("mod state;"));
+            assert!(main_rs.contains(
+                "    let state = state::AppState::new();\n\n    // Compose the routes\n    let app = Router::new();\n    let app = app.layer(middleware::from_fn_with_state(state.clone(), middlewares::keys_middleware));"
+            ));
+        });
+    }
+
+    #[test]
+    fn test_generate_layer_creates_layers_module_and_attaches_it() {
+        in_temp_project("layer", |root| {
+            fs::write(root.join("src/main.rs"), "use axum::Router;\n\nasync fn main() {\n    let app = Router::new();\n}\n").unwrap();
+            fs::write(root.join("Cargo.toml"), "[package]\nname = \"app\"\n\n[dependencies]\n").unwrap();
+            generate_layer("audit".into(), MiddlewareScope::App, LayerOrder::Outermost).unwrap();
+
+            let layer = fs::read_to_string(root.join("src/layers/audit.rs")).unwrap();
+            assert!(layer.contains("impl<S> Layer<S> for AuditLayer {"));
+            assert!(layer.contains("type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;"));
+            assert!(fs::read_to_string(root.join("src/layers/mod.rs")).unwrap().contains("pub mod audit;"));
+            assert!(fs::read_to_string(root.join("Cargo.toml")).unwrap().contains("tower = \"0.5.3\""));
+            let main_rs = fs::read_to_string(root.join("src/main.rs")).unwrap();
+            assert!(main_rs.contains("mod layers;"));
+            assert!(main_rs.contains("    let app = app.layer(layers::audit::AuditLayer::new(layers::audit::AuditConfig::default()));"));
+        });
+    }
+
+    #[test]
+    fn test_api_key_middlewares_share_config_and_verification() {
+        in_temp_project("middleware-api-key", |root| {
+            fs::write(root.join("src/main.rs"), "use axum::Router;\nmod billing;\n\nasync fn main() {\n    let app = Router::new();\n}\n").unwrap();
+            fs::create_dir_all(root.join("src/billing")).unwrap();
+            fs::write(root.join("src/billing/mod.rs"), "pub fn routes() -> Router {\n    Router::new()\n}\n").unwrap();
+            assert!(generate_middleware(options("internal", MiddlewareKind::ApiKey, MiddlewareScope::App, LayerOrder::Outermost)).is_err());
+            assert!(!root.join("src/middlewares.rs").exists());
+
+            fs::write(root.join("src/config.rs"), config_template("app", &ProjectConfig::default())).unwrap();
+            generate_middleware(options("internal", MiddlewareKind::ApiKey, MiddlewareScope::App, LayerOrder::Outermost)).unwrap();
+            let module = MiddlewareScope::Module("billing".into());
+            generate_middleware(options("billing", MiddlewareKind::ApiKey, module, LayerOrder::Outermost)).unwrap();
+
+            let middlewares = fs::read_to_string(root.join("src/middlewares.rs")).unwrap();
+            assert_eq!(middlewares.matches(API_KEY_MARKER).count(), 1);
+            assert!(middlewares.contains("scope == \"billing\" || scope == \"*\""));
+            let config = fs::read_to_string(root.join("src/config.rs")).unwrap();
+            assert_eq!(config.matches("pub api_keys: Vec<ApiKey>,").count(), 1);
+            assert_eq!(config.matches("pub struct ApiKey {").count(), 1);
+            // no keys by default: the empty list is dropped when the defaults are serialized, and loads back through `#[serde(default)]`
+            assert!(config.contains("#[serde(default)]\npub struct AppConfig {"));
+            assert!(config.contains("            api_keys: Default::default(),\n"));
+            syn::parse_file(&config).unwrap();
+            assert!(fs::read_to_string(root.join("config/default.toml")).unwrap().contains("# [[api_keys]]"));
+            let mod_rs = fs::read_to_string(root.join("src/billing/mod.rs")).unwrap();
+            assert!(mod_rs.contains(".layer(axum::middleware::from_fn(crate::middlewares::billing_middleware))"));
+        });
+    }
+
+    #[test]
+    fn test_standalone_items_follow_the_project_id() {
+        in_temp_project("items-uuid", |root| {
+            fs::write(root.join("Cargo.toml"), "[package]\nname = \"app\"\n\n[dependencies]\n").unwrap();
+            ProjectConfig { id: Some(IdStrategy::Uuid), ..ProjectConfig::default() }.save(root).unwrap();
+
+            generate_controller("hero".to_string(), None).unwrap();
+            generate_service("hero".to_string(), None).unwrap();
+            generate_entity("hero".to_string(), vec![], None, false, false).unwrap();
+            generate_entity("villain".to_string(), vec![], Some(IdStrategy::U32), false, false).unwrap();
+
+            let controller = fs::read_to_string(root.join("hero/hero_controller.rs")).unwrap();
+            assert_eq!(controller.matches("Path(id): Path<uuid::Uuid>").count(), 3);
+            let service = fs::read_to_string(root.join("hero/hero_service.rs")).unwrap();
+            assert!(service.contains("pub async fn find_one(id: uuid::Uuid) -> String {"));
+            assert!(fs::read_to_string(root.join("hero/hero_entity.rs")).unwrap().contains("pub id: uuid::Uuid,"));
+            assert!(fs::read_to_string(root.join("villain/villain_entity.rs")).unwrap().contains("pub id: u32,"));
+            assert!(fs::read_to_string(root.join("Cargo.toml")).unwrap().contains("uuid = "));
+        });
+    }
+}
@@ -1,32 +1,53 @@
 use anyhow::Result;
 use std::fs;
 use std::io::{Read, Write};
-use std::path::{Path, PathBuf};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+use crate::utils::common::capitalize;
 use crate::utils::file::*;
 
+use crate::utils::field::Field;
+use crate::utils::project::IdStrategy;
+
+use super::generate_item::insert_mod_decl;
+use super::generate_rbac::generate_rbac;
+use super::templates::dependencies::{ensure_dependency, id_dependencies, timestamp_dependencies};
+use super::templates::rbac_templates::permissions_template;
+
 use super::templates::generate_module_templates::{
     controller_template,
     dto_template,
     entity_template,
+    migration_template,
+    openapi_template,
+    pagination_template,
     service_template,
+    ModuleSpec,
 };
 
 /// Generate controller inside src/{module}/{module}_controller.rs and ensure mod declarations.
-pub fn generate_controller(name: String) -> Result<()> {
+pub fn generate_controller(spec: &ModuleSpec) -> Result<()> {
+    let name = &spec.name;
     println!("Generating controller: {}", name);
 
     // 1️⃣ ensure module dir and mod.rs
-    let mdir = ensure_dir(&module_dir(&name))?;
+    let mdir = ensure_dir(&spec.dir())?;
     let mod_rs = ensure_mod_rs(&mdir)?;
 
     // 2️⃣ ensure module is published at crate root
-    ensure_root_mod(&name)?;
+    declare_module(spec)?;
+
+    ensure_pagination(spec)?;
 
     // 3️⃣ create controller file path ({module}_controller.rs)
     let filename = mdir.join(format!("{}_controller.rs", name));
 
     // write controller content
-    fs::write(&filename, controller_template(&name))?;
+    fs::write(&filename, controller_template(spec))?;
+    if spec.permissions {
+        fs::write(mdir.join(format!("{}_permissions.rs", name)), permissions_template(name))?;
+        ensure_pub_mod_decl(&mod_rs, &format!("{}_permissions", name))?;
+    }
 
     // 4️⃣ ensure `pub mod {module}_controller;` is present in src/{module}/mod.rs
     let mod_name = format!("{}_controller", name);
@@ -68,20 +89,22 @@ pub fn routes() -> Router {{
 }
 
 /// Generate service inside src/{module}/{module}_service.rs and ensure mod declarations.
-pub fn generate_service(name: String) -> Result<()> {
+pub fn generate_service(spec: &ModuleSpec) -> Result<()> {
+    let name = &spec.name;
     println!("Generating service: {}", name);
 
     // ensure module dir and mod.rs
-    let mdir = ensure_dir(&module_dir(&name))?;
+    let mdir = ensure_dir(&spec.dir())?;
     let mod_rs = ensure_mod_rs(&mdir)?;
 
     // ensure root has this module
-    ensure_root_mod(&name)?;
+    declare_module(spec)?;
 
     // create service file path ({module}_service.rs)
     let filename = mdir.join(format!("{}_service.rs", name));
 
-    fs::write(&filename, service_template(&name))?;
+    ensure_pagination(spec)?;
+    fs::write(&filename, service_template(spec))?;
 
     // ensure `pub mod {module}_service;` in src/{module}/mod.rs
     let mod_name = format!("{}_service", name);
@@ -91,17 +114,49 @@ pub fn generate_service(name: String) -> Result<()> {
     Ok(())
 }
 
+/// Declare the module at the crate root (src/lib.rs), or in its version namespace and the namespace at the root.
+fn declare_module(spec: &ModuleSpec) -> Result<()> {
+    match &spec.version {
+        Some(version) => declare_versioned_module(version, &spec.name),
+        None => ensure_root_mod(&spec.name),
+    }
+}
+
+/// Declare `pub mod {name};` in src/{version}/mod.rs, and the `{version}` namespace at the crate root.
+pub fn declare_versioned_module(version: &str, name: &str) -> Result<()> {
+    let version_dir = ensure_dir(&module_dir(version))?;
+    ensure_pub_mod_decl(&ensure_mod_rs(&version_dir)?, name)?;
+    ensure_root_mod(version)
+}
+
+/// Write src/pagination.rs, which the controllers and services of every module share, unless it exists.
+fn ensure_pagination(spec: &ModuleSpec) -> Result<()> {
+    let path = Path::new("src/pagination.rs");
+    if path.exists() {
+        return Ok(());
+    }
+    fs::write(path, pagination_template(&spec.project))?;
+    ensure_root_mod("pagination")?;
+    let main_path = Path::new("src/main.rs");
+    if let Ok(mut main_rs) = fs::read_to_string(main_path) {
+        insert_mod_decl(&mut main_rs, "mod pagination;");
+        fs::write(main_path, main_rs)?;
+    }
+    Ok(())
+}
+
 /// Generate DTO file inside src/{module}/dto/{module}_dto.rs.
 /// Ensure src/{module}/dto/mod.rs and src/{module}/mod.rs include proper declarations.
-pub fn generate_dto(name: String) -> Result<()> {
+pub fn generate_dto(spec: &ModuleSpec) -> Result<()> {
+    let name = &spec.name;
     println!("Generating dto: {}", name);
 
     // module dir
-    let mdir = ensure_dir(&module_dir(&name))?;
+    let mdir = ensure_dir(&spec.dir())?;
     let mod_rs = ensure_mod_rs(&mdir)?;
 
     // ensure module declared at crate root
-    ensure_root_mod(&name)?;
+    declare_module(spec)?;
 
     // ensure dto subdir and mod.rs
     let dto_dir = ensure_dir(&mdir.join("dto"))?;
@@ -111,7 +166,7 @@ pub fn generate_dto(name: String) -> Result<()> {
     let dto_filename = format!("{}_dto.rs", name);
     let full_path = dto_dir.join(&dto_filename);
 
-    fs::write(&full_path, dto_template(&name))?;
+    fs::write(&full_path, dto_template(spec))?;
 
     // ensure dto mod is included in module mod.rs: `pub mod dto;`
     ensure_pub_mod_decl(&mod_rs, "dto")?;
@@ -126,15 +181,16 @@ pub fn generate_dto(name: String) -> Result<()> {
 
 /// Generate entity file inside src/{module}/entities/{module}_entity.rs.
 /// Ensure src/{module}/entities/mod.rs and src/{module}/mod.rs include proper declarations.
-pub fn generate_entity(name: String) -> Result<()> {
+pub fn generate_entity(spec: &ModuleSpec) -> Result<()> {
+    let name = &spec.name;
     println!("Generating entity: {}", name);
 
     // module dir
-    let mdir = ensure_dir(&module_dir(&name))?;
+    let mdir = ensure_dir(&spec.dir())?;
     let mod_rs = ensure_mod_rs(&mdir)?;
 
     // ensure module declared at crate root
-    ensure_root_mod(&name)?;
+    declare_module(spec)?;
 
     // ensure entities subdir and mod.rs
     let ent_dir = ensure_dir(&mdir.join("entities"))?;
@@ -144,7 +200,7 @@ pub fn generate_entity(name: String) -> Result<()> {
     let ent_filename = format!("{}_entity.rs", name);
     let full_path = ent_dir.join(&ent_filename);
 
-    fs::write(&full_path, entity_template(&name))?;
+    fs::write(&full_path, entity_template(spec))?;
 
     // ensure entities mod is included in module mod.rs: `pub mod entities;`
     ensure_pub_mod_decl(&mod_rs, "entities")?;
@@ -158,22 +214,51 @@ pub fn generate_entity(name: String) -> Result<()> {
 }
 
 
-pub fn generate_module(name: String) -> Result<()> {
-    println!("Generating module: {}", name);
+/// Generate the SQL migration creating the module's table inside migrations/.
+pub fn generate_migration(spec: &ModuleSpec) -> Result<()> {
+    write_migration(&format!("create_{}s", spec.name), &migration_template(spec)?)
+}
 
-    // 1️⃣ Generate all module components first
-    generate_service(name.clone())?;
-    generate_dto(name.clone())?;
-    generate_entity(name.clone())?;
-    generate_controller(name.clone())?;
+/// Write migrations/{version}_{name}.sql, e.g. `create_heros`, unless a migration with that name exists.
+pub fn write_migration(name: &str, sql: &str) -> Result<()> {
+    let dir = ensure_dir(Path::new("migrations"))?;
+    let suffix = format!("_{}.sql", name);
+
+    // keep an existing migration instead of adding a second one doing the same
+    let exists = fs::read_dir(&dir)?
+        .flatten()
+        .any(|entry| entry.file_name().to_string_lossy().ends_with(&suffix));
+    if exists {
+        println!("Migration `{}` already exists, skipping", name);
+        return Ok(());
+    }
 
-    // 2️⃣ Update main.rs
-    let main_path = Path::new("src/main.rs");
-    let mut content = String::new();
-    fs::File::open(&main_path)?.read_to_string(&mut content)?;
+    // sqlx needs distinct versions and applies them in order, so never reuse or go below an existing one
+    let latest = fs::read_dir(&dir)?
+        .flatten()
+        .filter_map(|entry| {
+            let file_name = entry.file_name().to_string_lossy().into_owned();
+            file_name.split('_').next()?.parse::<u64>().ok()
+        })
+        .max();
+    let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+    let version = latest.map_or(now, |latest| now.max(latest + 1));
+    let path = dir.join(format!("{}{}", version, suffix));
+    fs::write(&path, sql)?;
+
+    println!("Migration created at {}", path.to_string_lossy());
+    Ok(())
+}
+
+
+/// Declare `mod {name};` in main.rs and mount `{name}::routes()` under `/{name}` in the router.
+/// A versioned `module_path` such as `v2::hero` declares `mod v2;` and is mounted under `/v2/hero`.
+pub fn nest_module(content: &mut String, module_path: &str) {
+    let root = module_path.split("::").next().unwrap_or(module_path);
+    let prefix = module_path.replace("::", "/");
 
-    // --- Insert `mod {name};` after last `use ...;` ---
-    let mod_decl = format!("mod {};", name);
+    // --- Insert `mod {root};` after last `use ...;` ---
+    let mod_decl = format!("mod {};", root);
     if !content.contains(&mod_decl) {
         if let Some(pos) = content.rfind("use ") {
             if let Some(end) = content[pos..].find(';') {
@@ -181,82 +266,157 @@ pub fn generate_module(name: String) -> Result<()> {
                 content.insert_str(insert_pos, &format!("\n{}", mod_decl));
             }
         } else {
-            content = format!("{}\n{}", mod_decl, content);
+            *content = format!("{}\n{}", mod_decl, content);
         }
     }
 
     // --- Insert `.nest("/name", name::routes())` inside Router::new() chain ---
-    let nest_line = format!("        .nest(\"/{0}\", {0}::routes())", name);
-    if !content.contains(&nest_line) {
-        if let Some(pos) = content.find("Router::new()") {
-            if let Some(chain_end) = content[pos..].find(';') {
-                let insert_pos = pos + chain_end;
-                content.insert_str(insert_pos, &format!("\n{}", nest_line));
-            }
+    let nest_line = format!("        .nest(\"/{}\", {}::routes())", prefix, module_path);
+    if !content.contains(&nest_line)
+        && let Some(pos) = content.find("Router::new()")
+        && let Some(chain_end) = content[pos..].find(';')
+    {
+        let insert_pos = pos + chain_end;
+        content.insert_str(insert_pos, &format!("\n{}", nest_line));
+    }
+}
+
+
+/// Answers for `axumate generate module`
+pub struct ModuleOptions {
+    pub name: String,
+    pub fields: Vec<Field>,
+    /// Guard the CRUD handlers with `{name}:create`, `{name}:read`, `{name}:update` and `{name}:delete`
+    pub permissions: bool,
+    /// API version namespace to place the module in, e.g. `v2`
+    pub version: Option<String>,
+    /// Id type of the module, instead of the project's
+    pub id: Option<IdStrategy>,
+    /// Add `created_at`/`updated_at`, maintained by the service
+    pub timestamps: bool,
+    /// Add `deleted_at`: `remove` marks rows deleted instead of deleting them
+    pub soft_delete: bool,
+}
+
+pub fn generate_module(options: ModuleOptions) -> Result<()> {
+    let ModuleOptions { name, fields, permissions, version, id, timestamps, soft_delete } = options;
+    println!("Generating module: {}", name);
+    let mut spec = ModuleSpec::new(&name, fields)?;
+    spec.permissions = permissions;
+    spec.version = version;
+    spec.timestamps = timestamps;
+    spec.soft_delete = soft_delete;
+    if let Some(id) = id {
+        spec.id = id;
+    }
+    spec.id.check(spec.project.database)?;
+    for dep in id_dependencies(spec.id, &spec.project) {
+        ensure_dependency(&dep)?;
+    }
+    if timestamps || soft_delete {
+        for dep in timestamp_dependencies(&spec.project) {
+            ensure_dependency(&dep)?;
         }
     }
+    if permissions && !Path::new("src/rbac/mod.rs").exists() {
+        generate_rbac()?;
+    }
 
-    // Write back the updated main.rs
-    fs::File::create(&main_path)?.write_all(content.as_bytes())?;
+    // 1️⃣ Generate all module components first
+    if spec.project.database.is_sql() {
+        generate_migration(&spec)?;
+    }
+    generate_service(&spec)?;
+    generate_dto(&spec)?;
+    generate_entity(&spec)?;
+    generate_controller(&spec)?;
+
+    if spec.project.openapi {
+        let mod_rs = spec.dir().join("mod.rs");
+        let mut mod_content = fs::read_to_string(&mod_rs)?;
+        if !mod_content.contains("derive(utoipa::OpenApi)") {
+            mod_content.push_str(&openapi_template(&spec));
+            fs::write(&mod_rs, mod_content)?;
+        }
+    }
+
+    // 2️⃣ Update main.rs
+    mount_module(&spec.module_path(), spec.project.openapi)?;
 
-    println!("main.rs updated with module `{}`", name);
+    println!("main.rs updated with module `{}`", spec.module_path());
     println!("Module {} generated successfully!", name);
 
     Ok(())
 }
 
+/// Serve the module at `module_path` (e.g. `hero` or `v2::hero`) from main.rs, merging its OpenAPI docs under the same prefix.
+pub fn mount_module(module_path: &str, openapi: bool) -> Result<()> {
+    let main_path = Path::new("src/main.rs");
+    let mut content = String::new();
+    fs::File::open(main_path)?.read_to_string(&mut content)?;
+
+    nest_module(&mut content, module_path);
+
+    // --- Merge the module's OpenAPI docs under the same prefix ---
+    let name = module_path.rsplit("::").next().unwrap_or(module_path);
+    let doc_line = format!("        .nest(\"/{}\", {}::{}Api::openapi())", module_path.replace("::", "/"), module_path, capitalize(name));
+    if openapi
+        && !content.contains(&doc_line)
+        && let Some(pos) = content.find("ApiDoc::openapi()")
+        && let Some(chain_end) = content[pos..].find(';')
+    {
+        let insert_pos = pos + chain_end;
+        content.insert_str(insert_pos, &format!("\n{}", doc_line));
+    }
+
+    // Write back the updated main.rs
+    fs::File::create(main_path)?.write_all(content.as_bytes())?;
+    Ok(())
+}
+
 
 //===================================tests==========================================
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::fs;
-    use std::env;
+    use crate::utils::testing::in_temp_project;
 
     #[test]
     fn test_generate_controller_creates_file() {
-        let dir = env::temp_dir();
-        let filename = dir.join("test_controller.rs");
-        let filename_str = filename.to_str().unwrap();
-
-        generate_controller("test".into()).unwrap();
-
-        assert!(filename.exists());
-
-        let _ = fs::remove_file(filename_str);
+        in_temp_project("module-controller", |root| {
+            generate_controller(&ModuleSpec::new("test", vec![]).unwrap()).unwrap();
+
+            assert!(root.join("src/test/test_controller.rs").exists());
+            let mod_rs = fs::read_to_string(root.join("src/test/mod.rs")).unwrap();
+            assert!(mod_rs.contains("pub fn routes() -> Router"));
+            assert!(root.join("src/pagination.rs").exists());
+            assert!(fs::read_to_string(root.join("src/lib.rs")).unwrap().contains("pub mod pagination;"));
+        });
     }
 
     #[test]
     fn test_generate_service_creates_file() {
-        let dir = env::temp_dir();
-        let filename = dir.join("test_service.rs");
-        let filename_str = filename.to_str().unwrap();
-
-        generate_service("test".into()).unwrap();
-
-        assert!(filename.exists());
+        in_temp_project("module-service", |root| {
+            generate_service(&ModuleSpec::new("test", vec![]).unwrap()).unwrap();
 
-        let _ = fs::remove_file(filename_str);
+            assert!(root.join("src/test/test_service.rs").exists());
+        });
     }
 
     #[test]
     fn test_generate_dto_creates_file() {
-        let dir = env::temp_dir();
-        let filename = dir.join("test_dto.rs");
-        let filename_str = filename.to_str().unwrap();
-        generate_dto("test".into()).unwrap();
-        assert!(filename.exists());
-        let _ = fs::remove_file(filename_str);
+        in_temp_project("module-dto", |root| {
+            generate_dto(&ModuleSpec::new("test", vec![]).unwrap()).unwrap();
+            assert!(root.join("src/test/dto/test_dto.rs").exists());
+        });
     }
 
     #[test]
     fn test_generate_entity_creates_file() {
-        let dir = env::temp_dir();
-        let filename = dir.join("test_entity.rs");
-        let filename_str = filename.to_str().unwrap();
-        generate_entity("test".into()).unwrap();
-        assert!(filename.exists());
-        let _ = fs::remove_file(filename_str);
+        in_temp_project("module-entity", |root| {
+            generate_entity(&ModuleSpec::new("test", vec![]).unwrap()).unwrap();
+            assert!(root.join("src/test/entities/test_entity.rs").exists());
+        });
     }
 }
@@ -0,0 +1,91 @@
+use anyhow::{bail, Result};
+use std::fs;
+use std::path::Path;
+
+use crate::utils::field::Field;
+use crate::utils::file::*;
+use crate::utils::project::ProjectConfig;
+
+use super::generate_config::generate_config_key;
+use super::generate_item::insert_mod_decl;
+use super::generate_module::write_migration;
+use super::templates::rbac_templates::*;
+
+/// Generate src/rbac/: the roles table, the `Permission` trait, the `RequirePermission` extractor and a role store.
+/// Needs the auth module, whose `CurrentUser` tells who is asking.
+pub fn generate_rbac() -> Result<()> {
+    println!("Generating rbac");
+    if !module_dir("auth").join("current_user.rs").exists() {
+        bail!("RBAC builds on the auth module; run `axumate generate auth` first");
+    }
+    let project = ProjectConfig::load()?;
+
+    let rbac_dir = ensure_dir(&module_dir("rbac"))?;
+    let mod_rs = rbac_dir.join("mod.rs");
+    if mod_rs.exists() {
+        // it holds the project's own roles
+        println!("{} already exists, keeping it", mod_rs.to_string_lossy());
+    } else {
+        fs::write(&mod_rs, rbac_mod_template(&project))?;
+    }
+    fs::write(rbac_dir.join("role_store.rs"), role_store_template(&project))?;
+    ensure_root_mod("rbac")?;
+
+    if project.database.is_sql() {
+        write_migration("create_user_roles", &user_roles_migration_template(project.database))?;
+    }
+
+    // the user with this email is an admin, so there is a way in before roles are assigned
+    let config = fs::read_to_string("src/config.rs").unwrap_or_default();
+    if !config.contains("    pub admin_email:") {
+        generate_config_key(Field { name: "admin_email".to_string(), ty: "String".to_string() }, None)?;
+    }
+
+    let main_path = Path::new("src/main.rs");
+    let mut main_rs = fs::read_to_string(main_path)?;
+    insert_mod_decl(&mut main_rs, "mod rbac;");
+    fs::write(main_path, main_rs)?;
+
+    println!("RBAC generated successfully! Edit ROLES in src/rbac/mod.rs and set `admin_email` in config");
+    Ok(())
+}
+
+
+//===================================tests==========================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::generate_module::{generate_module, ModuleOptions};
+    use crate::utils::testing::in_temp_project;
+
+    #[test]
+    fn test_module_permissions_guard_crud_handlers() {
+        in_temp_project("rbac", |root| {
+            fs::write(root.join("src/main.rs"), "use axum::Router;\n\nasync fn main() {\n    let app = Router::new();\n}\n").unwrap();
+            fs::write(
+                root.join("src/config.rs"),
+                "pub struct AppConfig {\n    pub port: u16,\n}\n\nimpl Default for AppConfig {\n    fn default() -> Self {\n        AppConfig {\n            port: 8080,\n        }\n    }\n}\n",
+            )
+            .unwrap();
+
+            let options = || ModuleOptions { name: "hero".into(), fields: vec![], permissions: true, version: None, id: None, timestamps: false, soft_delete: false };
+            assert!(generate_module(options()).is_err(), "rbac needs the auth module");
+
+            fs::create_dir_all(root.join("src/auth")).unwrap();
+            fs::write(root.join("src/auth/current_user.rs"), "").unwrap();
+            generate_module(options()).unwrap();
+
+            let controller = fs::read_to_string(root.join("src/hero/hero_controller.rs")).unwrap();
+            assert!(controller.contains("use crate::hero::hero_permissions::{HeroCreate, HeroRead, HeroUpdate, HeroDelete};"));
+            assert!(controller.contains("pub async fn find_all(_permission: RequirePermission<HeroRead>, Query(query): Query<HeroListQuery>) ->"));
+            assert!(controller.contains("pub async fn remove(_permission: RequirePermission<HeroDelete>, Path(id)"));
+
+            let permissions = fs::read_to_string(root.join("src/hero/hero_permissions.rs")).unwrap();
+            assert!(permissions.contains("const NAME: &'static str = \"hero:update\";"));
+            assert!(fs::read_to_string(root.join("src/hero/mod.rs")).unwrap().contains("pub mod hero_permissions;"));
+            assert!(fs::read_to_string(root.join("src/main.rs")).unwrap().contains("mod rbac;"));
+            assert!(fs::read_to_string(root.join("src/config.rs")).unwrap().contains("pub admin_email: String,"));
+        });
+    }
+}
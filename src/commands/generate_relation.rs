@@ -0,0 +1,227 @@
+use anyhow::{bail, Context, Result};
+use clap::ValueEnum;
+use std::fs;
+use std::path::Path;
+use syn::Item;
+
+use crate::utils::ast::*;
+use crate::utils::common::capitalize;
+use crate::utils::field::Field;
+use crate::utils::file::module_dir;
+use crate::utils::project::{IdStrategy, ProjectConfig};
+
+use super::generate_module::write_migration;
+use super::generate_route::add_route;
+use super::templates::generate_module_templates::{service_template, ModuleSpec, TIMESTAMP_FIELDS};
+use super::templates::relation_templates::*;
+use super::templates::route_templates::Endpoint;
+
+/// How the first module of `generate relation` relates to the second
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum RelationKind {
+    /// `hero has-many power`: every power belongs to one hero
+    HasMany,
+    /// `power belongs-to hero`: the same relation, named from the other side
+    BelongsTo,
+}
+
+/// Relate two existing modules one-to-many:
+/// a `{parent}_id` foreign key on the child and `GET /{parent}/{id}/{child}s` on the parent.
+pub fn generate_relation(from: String, kind: RelationKind, to: String) -> Result<()> {
+    let (parent, child) = match kind {
+        RelationKind::HasMany => (from, to),
+        RelationKind::BelongsTo => (to, from),
+    };
+    println!("Generating relation: {} has many {}", parent, child);
+    if parent == child {
+        bail!("A module cannot be related to itself");
+    }
+    let project = ProjectConfig::load()?;
+    let (cap, parent_cap) = (capitalize(&child), capitalize(&parent));
+
+    let read = |module: &str, file: &str| -> Result<(std::path::PathBuf, String)> {
+        let path = module_dir(module).join(file);
+        let content = fs::read_to_string(&path).with_context(|| format!("{} not found; generate module `{}` first", path.to_string_lossy(), module))?;
+        Ok((path, content))
+    };
+    let (entity_path, mut entity) = read(&child, &format!("entities/{}_entity.rs", child))?;
+    let (parent_entity_path, parent_entity) = read(&parent, &format!("entities/{}_entity.rs", parent))?;
+    let (dto_path, mut dto) = read(&child, &format!("dto/{}_dto.rs", child))?;
+    let (child_service_path, mut child_service) = read(&child, &format!("{}_service.rs", child))?;
+    let (controller_path, mut controller) = read(&parent, &format!("{}_controller.rs", parent))?;
+    let (parent_service_path, mut parent_service) = read(&parent, &format!("{}_service.rs", parent))?;
+    let (mod_path, mut mod_rs) = read(&parent, "mod.rs")?;
+
+    // 1️⃣ check everything and route the endpoint (in memory)
+    let parent_id = entity_id(&parent_entity_path, &parent_entity, &parent_cap)?;
+    let parent_fields = struct_fields(&parse_file(&parent_entity_path, &parent_entity)?, &parent_entity, &parent_cap).unwrap_or_default();
+    let entity_ast = parse_file(&entity_path, &entity)?;
+    let fields = struct_fields(&entity_ast, &entity, &cap)
+        .with_context(|| format!("No `{}` struct in {}", cap, entity_path.to_string_lossy()))?;
+    let has = |fields: &[(String, String)], field: &str| fields.iter().any(|(name, _)| name == field);
+    let relation = Relation {
+        parent: parent.clone(),
+        child: child.clone(),
+        id: parent_id,
+        database: project.database,
+        parent_soft_delete: has(&parent_fields, "deleted_at"),
+        child_soft_delete: has(&fields, "deleted_at"),
+    };
+    let (foreign_key, finder) = (relation.foreign_key(), relation.finder());
+    let id = parent_id.rust_type();
+    let controller_ast = parse_file(&controller_path, &controller)?;
+    if find_fn(&controller_ast, &finder).is_some() {
+        bail!("{} already has `{}`; `{}` and `{}` are related", controller_path.to_string_lossy(), finder, parent, child);
+    }
+    if fields.iter().any(|(name, _)| *name == foreign_key) {
+        bail!("`{}` already has a `{}` field", cap, foreign_key);
+    }
+    let child_id = entity_id(&entity_path, &entity, &cap)?;
+    let endpoint = Endpoint {
+        module: parent.clone(),
+        action: finder.clone(),
+        method: "get",
+        path: format!("/{{id}}/{}s", child),
+        params: vec![("id".to_string(), id.to_string())],
+        body: None,
+        query: None,
+    };
+    add_route(&mut mod_rs, &mod_path, &endpoint, project.openapi)?;
+
+    // 2️⃣ the foreign key on the child's entity and DTOs
+    add_struct_field(&mut entity, &cap, &format!("pub {}: {}", foreign_key, relation.foreign_key_type()));
+    parse_file(&dto_path, &dto)?;
+    for (dto_struct, field) in [
+        (format!("Create{}Dto", cap), format!("pub {}: {}", foreign_key, id)),
+        (format!("Update{}Dto", cap), format!("pub {}: Option<{}>", foreign_key, id)),
+        (format!("{}ListQuery", cap), format!("/// Only {}s of this {}\n    pub {}: Option<{}>", child, parent, foreign_key, id)),
+    ] {
+        if !add_struct_field(&mut dto, &dto_struct, &field) {
+            println!("No `{}` in {}, add `{}` to your own DTOs", dto_struct, dto_path.to_string_lossy(), foreign_key);
+        }
+    }
+
+    // 3️⃣ the child's service stores the key: regenerated when it is still as generated, left to you otherwise
+    let mut spec = ModuleSpec {
+        name: child.clone(),
+        fields: fields
+            .iter()
+            .filter(|(name, _)| name != "id" && !TIMESTAMP_FIELDS.contains(&name.as_str()))
+            .map(|(name, ty)| Field::new(name, ty))
+            .collect(),
+        project: project.clone(),
+        permissions: false,
+        version: None,
+        id: child_id,
+        timestamps: has(&fields, "created_at"),
+        soft_delete: relation.child_soft_delete,
+    };
+    let untouched = service_template(&spec) == child_service;
+    if untouched {
+        spec.fields.push(Field::new(&foreign_key, id));
+        child_service = service_template(&spec);
+    }
+    let child_ast = parse_file(&child_service_path, &child_service)?;
+    let child_async = child_ast.items.iter().any(|item| matches!(item, Item::Fn(f) if f.sig.asyncness.is_some()));
+    if find_fn(&child_ast, &format!("find_by_{}", parent)).is_none() {
+        child_service.push_str(&child_finder_template(&relation, project.tracing, child_async));
+    }
+
+    // 4️⃣ the parent's service and controller list the children
+    let parent_ast = parse_file(&parent_service_path, &parent_service)?;
+    let parent_async = parent_ast.items.iter().any(|item| matches!(item, Item::Fn(f) if f.sig.asyncness.is_some()));
+    let (child_service_mod, parent_service_mod) = (format!("{}_service", child), format!("{}_service", parent));
+    let child_imports = [
+        (cap.as_str(), format!("use crate::{0}::entities::{0}_entity::{1};", child, cap)),
+        (child_service_mod.as_str(), format!("use crate::{}::{};", child, child_service_mod)),
+    ];
+    add_imports(&mut parent_service, &parent_ast, &child_imports);
+    parent_service.push_str(&parent_finder_template(&relation, project.tracing, parent_async));
+
+    add_imports(
+        &mut controller,
+        &controller_ast,
+        &[
+            ("Path", "use axum::extract::Path;".to_string()),
+            ("StatusCode", "use axum::http::StatusCode;".to_string()),
+            ("Json", "use axum::Json;".to_string()),
+            (parent_service_mod.as_str(), format!("use crate::{}::{};", parent, parent_service_mod)),
+            child_imports[0].clone(),
+        ],
+    );
+    controller.push_str(&relation_handler_template(&relation, project.openapi, parent_async));
+
+    // 5️⃣ the column, with its migration
+    if project.database.is_sql() {
+        write_migration(&format!("add_{}_to_{}s", foreign_key, child), &relation_migration_template(&relation))?;
+    }
+
+    fs::write(&entity_path, entity)?;
+    fs::write(&dto_path, dto)?;
+    fs::write(&child_service_path, child_service)?;
+    fs::write(&parent_service_path, parent_service)?;
+    fs::write(&controller_path, controller)?;
+    fs::write(&mod_path, mod_rs)?;
+
+    if !untouched {
+        println!(
+            "{} has been edited, so it was left as is: set `{}` in its create and update functions",
+            child_service_path.to_string_lossy(),
+            foreign_key
+        );
+    }
+    println!("Relation generated successfully! {} has many {}s at GET /{}/{{id}}/{}s", parent_cap, child, parent, child);
+    Ok(())
+}
+
+/// The id strategy of the entity `name`, from the type of its `id` field
+pub fn entity_id(path: &Path, content: &str, name: &str) -> Result<IdStrategy> {
+    let file = parse_file(path, content)?;
+    let fields = struct_fields(&file, content, name).with_context(|| format!("No `{}` struct in {}", name, path.to_string_lossy()))?;
+    fields
+        .iter()
+        .find(|(field, _)| field == "id")
+        .and_then(|(_, ty)| IdStrategy::from_type(ty))
+        .with_context(|| format!("`{}` has no `id` of a type axumate generates", name))
+}
+
+//===================================tests==========================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::generate_module::{generate_module, ModuleOptions};
+    use crate::utils::testing::in_temp_project;
+
+    #[test]
+    fn test_generate_relation_adds_foreign_key_and_nested_route() {
+        in_temp_project("relation", |root| {
+            fs::write(root.join("src/main.rs"), "use axum::Router;\n\nasync fn main() {\n    let app = Router::new();\n}\n").unwrap();
+            for name in ["hero", "power"] {
+                generate_module(ModuleOptions { name: name.to_string(), fields: vec![], permissions: false, version: None, id: None, timestamps: false, soft_delete: false }).unwrap();
+            }
+
+            generate_relation("power".to_string(), RelationKind::BelongsTo, "hero".to_string()).unwrap();
+            assert!(generate_relation("hero".to_string(), RelationKind::HasMany, "power".to_string()).is_err());
+
+            let src = root.join("src");
+            let files = ["hero/mod.rs", "hero/hero_controller.rs", "hero/hero_service.rs", "power/power_service.rs", "power/entities/power_entity.rs", "power/dto/power_dto.rs"]
+                .map(|file| {
+                    let content = fs::read_to_string(src.join(file)).unwrap();
+                    syn::parse_file(&content).unwrap_or_else(|err| panic!("{} no longer parses: {}", file, err));
+                    content
+                });
+            let [mod_rs, controller, hero_service, power_service, entity, dto] = files;
+
+            assert!(mod_rs.contains(".route(\"/{id}/powers\", get(hero_controller::find_powers))"));
+            assert!(controller.contains("pub async fn find_powers(Path(id): Path<u32>) -> Result<Json<Vec<Power>>, (StatusCode, String)> {"));
+            assert!(controller.contains("use crate::power::entities::power_entity::Power;"));
+            assert!(hero_service.contains("Some(power_service::find_by_hero(id).await)"));
+            assert!(power_service.contains("pub async fn find_by_hero(hero_id: u32) -> Vec<Power> {"));
+            assert!(power_service.contains("hero_id: dto.hero_id"));
+            assert!(entity.contains("pub hero_id: u32,"));
+            assert_eq!(dto.matches("pub hero_id: Option<u32>,").count(), 2);
+            assert!(dto.contains("pub hero_id: u32,"));
+        });
+    }
+}
@@ -0,0 +1,330 @@
+use anyhow::{bail, Context, Result};
+use clap::ValueEnum;
+use proc_macro2::TokenTree;
+use std::fs;
+use std::path::Path;
+use syn::{Expr, File, Item, Lit, Meta, Stmt};
+
+use crate::utils::ast::*;
+use crate::utils::common::capitalize;
+use crate::utils::file::module_dir;
+use crate::utils::project::ProjectConfig;
+
+use super::generate_relation::entity_id;
+use super::templates::route_templates::*;
+
+/// HTTP method of a generated route
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum HttpMethod {
+    #[default]
+    Get,
+    Post,
+    Put,
+    Patch,
+    Delete,
+}
+
+impl HttpMethod {
+    /// Name of the axum method router, e.g. `post`
+    pub fn router(self) -> &'static str {
+        match self {
+            HttpMethod::Get => "get",
+            HttpMethod::Post => "post",
+            HttpMethod::Put => "put",
+            HttpMethod::Patch => "patch",
+            HttpMethod::Delete => "delete",
+        }
+    }
+}
+
+/// Answers for `axumate generate route`
+pub struct RouteOptions {
+    pub module: String,
+    pub action: String,
+    pub method: HttpMethod,
+    /// Path under the module's prefix, `/{action}` when not given
+    pub path: Option<String>,
+    /// Type of the JSON body, added to the module's DTOs when it does not exist
+    pub body: Option<String>,
+    /// Type of the query string, added to the module's DTOs when it does not exist
+    pub query: Option<String>,
+}
+
+/// Add an endpoint to an existing module: a handler in its controller, a stub in its service
+/// and a `.route(...)` in its routes(). The files are edited through their syntax trees.
+pub fn generate_route(options: RouteOptions) -> Result<()> {
+    let RouteOptions { module, action, method, path, body, query } = options;
+    println!("Generating route: {}::{}", module, action);
+    for ident in [Some(&action), body.as_ref(), query.as_ref()].into_iter().flatten() {
+        if syn::parse_str::<syn::Ident>(ident).is_err() {
+            bail!("`{}` is not a valid Rust identifier", ident);
+        }
+    }
+    let path = path.unwrap_or_else(|| format!("/{}", action));
+    if !path.starts_with('/') {
+        bail!("Route paths start with `/`, got `{}`", path);
+    }
+    let project = ProjectConfig::load()?;
+    // ids are typed like the module's entity id, or the project's when it has no entity
+    let entity_path = module_dir(&module).join("entities").join(format!("{}_entity.rs", module));
+    let id = match fs::read_to_string(&entity_path) {
+        Ok(entity) => entity_id(&entity_path, &entity, &capitalize(&module))?,
+        Err(_) => project.id_strategy(),
+    };
+    let endpoint = Endpoint { params: path_params(&path, id.rust_type()), module, action, method: method.router(), path, body, query };
+    let Endpoint { module, action, .. } = &endpoint;
+
+    let dir = module_dir(module);
+    let controller_path = dir.join(format!("{}_controller.rs", module));
+    let service_path = dir.join(format!("{}_service.rs", module));
+    let mod_path = dir.join("mod.rs");
+    let dto_path = dir.join("dto").join(format!("{}_dto.rs", module));
+    let mut controller = fs::read_to_string(&controller_path).with_context(|| format!("Controller of module `{}` not found", module))?;
+    let mut service = fs::read_to_string(&service_path).with_context(|| format!("Service of module `{}` not found", module))?;
+    let mut mod_rs = fs::read_to_string(&mod_path).with_context(|| format!("Module `{}` not found", module))?;
+    let mut dto = fs::read_to_string(&dto_path).unwrap_or_default();
+
+    // 1️⃣ check everything and route the endpoint (in memory)
+    let controller_ast = parse_file(&controller_path, &controller)?;
+    if find_fn(&controller_ast, action).is_some() {
+        bail!("{} already has a handler named `{}`", controller_path.to_string_lossy(), action);
+    }
+    let service_ast = parse_file(&service_path, &service)?;
+    add_route(&mut mod_rs, &mod_path, &endpoint, project.openapi)?;
+
+    // 2️⃣ body and query types the module does not have yet go to its DTO file
+    let dto_ast = parse_file(&dto_path, &dto)?;
+    let controller_names = imported_names(&controller_ast);
+    let mut dto_changed = false;
+    for (ty, is_query) in [(&endpoint.body, false), (&endpoint.query, true)] {
+        let Some(ty) = ty else { continue };
+        if has_struct(&dto_ast, ty) || controller_names.contains(ty) {
+            continue;
+        }
+        if !dto_path.exists() {
+            bail!("`{}` not found; declare it or create {}", ty, dto_path.to_string_lossy());
+        }
+        dto.push_str(&route_dto_template(ty, action, is_query, project.openapi));
+        dto_changed = true;
+    }
+    let dto_ast = parse_file(&dto_path, &dto)?;
+    let dto_imports: Vec<(&str, String)> = [&endpoint.body, &endpoint.query]
+        .into_iter()
+        .flatten()
+        .filter(|ty| has_struct(&dto_ast, ty))
+        .map(|ty| (ty.as_str(), format!("use crate::{0}::dto::{0}_dto::{1};", module, ty)))
+        .collect();
+
+    // 3️⃣ the service stub, async like the rest of the service
+    let existing = find_fn(&service_ast, action);
+    let is_async = match existing {
+        Some(f) => f.sig.asyncness.is_some(),
+        None => service_ast.items.iter().any(|item| matches!(item, Item::Fn(f) if f.sig.asyncness.is_some())),
+    };
+    if existing.is_none() {
+        add_imports(&mut service, &service_ast, &dto_imports);
+        service.push_str(&route_service_template(&endpoint, project.tracing, is_async));
+    }
+
+    // 4️⃣ the handler
+    let mut imports = vec![
+        ("StatusCode", "use axum::http::StatusCode;".to_string()),
+        ("Path", "use axum::extract::Path;".to_string()),
+        ("Query", "use axum::extract::Query;".to_string()),
+        ("Json", "use axum::Json;".to_string()),
+    ];
+    imports.retain(|(name, _)| match *name {
+        "Path" => !endpoint.params.is_empty(),
+        "Query" => endpoint.query.is_some(),
+        "Json" => endpoint.body.is_some(),
+        _ => true,
+    });
+    let service_mod = format!("{}_service", module);
+    imports.push((service_mod.as_str(), format!("use crate::{}::{};", module, service_mod)));
+    imports.extend(dto_imports.iter().cloned());
+    add_imports(&mut controller, &controller_ast, &imports);
+    let doc = if project.openapi {
+        let document_query = endpoint.query.as_ref().is_some_and(|ty| derives(&dto_ast, ty, "IntoParams"));
+        let document_body = endpoint.body.as_ref().is_some_and(|ty| derives(&dto_ast, ty, "ToSchema"));
+        route_doc_template(&endpoint, document_query, document_body)
+    } else {
+        String::new()
+    };
+    controller.push_str(&route_handler_template(&endpoint, &doc, is_async));
+
+    if dto_changed {
+        fs::write(&dto_path, dto)?;
+    }
+    fs::write(&service_path, service)?;
+    fs::write(&controller_path, controller)?;
+    fs::write(&mod_path, mod_rs)?;
+
+    println!(
+        "Route `{} /{}{}` generated successfully! Implement it in {}",
+        endpoint.method.to_uppercase(),
+        module,
+        endpoint.path,
+        service_path.to_string_lossy()
+    );
+    Ok(())
+}
+
+/// Parameters of an axum path such as `/{id}/tags/{tag}`.
+/// `id` and `*_id` are typed like the module's ids, the others are strings.
+fn path_params(path: &str, id_type: &str) -> Vec<(String, String)> {
+    path.split('/')
+        .filter_map(|segment| segment.strip_prefix('{')?.strip_suffix('}'))
+        .map(|name| {
+            let name = name.trim_start_matches('*');
+            let ty = if name == "id" || name.ends_with("_id") { id_type } else { "String" };
+            (name.to_string(), ty.to_string())
+        })
+        .collect()
+}
+
+/// Serve `endpoint` from the module's routes() in `mod_rs`, and list it in the module's OpenAPI docs.
+pub fn add_route(mod_rs: &mut String, mod_path: &Path, endpoint: &Endpoint, openapi: bool) -> Result<()> {
+    let mod_ast = parse_file(mod_path, mod_rs)?;
+    let (route_pos, route) = route_insertion(&mod_ast, endpoint)?;
+    mod_rs.insert_str(route_pos, &route);
+    if route.contains(".route(") {
+        // a new `.route(...)` starts from the free function of its method; the use items come before routes()
+        let method_router = (endpoint.method, format!("use axum::routing::{};", endpoint.method));
+        add_imports(mod_rs, &mod_ast, &[method_router]);
+    }
+    if openapi {
+        register_openapi_path(mod_rs, mod_path, endpoint)?;
+    }
+    Ok(())
+}
+
+/// Where in mod.rs to insert what, so that routes() serves the endpoint.
+/// A path already routed gets one more method; a new one gets a `.route(...)` after the others, inside any `.layer(...)`.
+fn route_insertion(mod_ast: &File, endpoint: &Endpoint) -> Result<(usize, String)> {
+    let routes = find_fn(mod_ast, "routes").with_context(|| format!("Module `{}` has no routes()", endpoint.module))?;
+    let Some(Stmt::Expr(router, None)) = routes.block.stmts.last() else {
+        bail!("routes() of module `{}` does not end with its Router", endpoint.module);
+    };
+    let handler = format!("{}_controller::{}", endpoint.module, endpoint.action);
+    let (calls, base) = method_chain(router);
+
+    for call in calls.iter().filter(|call| call.method == "route") {
+        let (Some(Expr::Lit(lit)), Some(method_router)) = (call.args.first(), call.args.iter().nth(1)) else {
+            continue;
+        };
+        if !matches!(&lit.lit, Lit::Str(s) if s.value() == endpoint.path) {
+            continue;
+        }
+        let (methods, first) = method_chain(method_router);
+        let first_method = match first {
+            Expr::Call(call) => match &*call.func {
+                Expr::Path(path) => path.path.get_ident().map(|ident| ident.to_string()),
+                _ => None,
+            },
+            _ => None,
+        };
+        let routed = methods.iter().map(|call| call.method.to_string()).chain(first_method);
+        if routed.into_iter().any(|method| method == endpoint.method) {
+            bail!("`{} {}` is already routed in module `{}`", endpoint.method.to_uppercase(), endpoint.path, endpoint.module);
+        }
+        // before any route_layer, so the layer covers the new method too
+        let end = methods
+            .iter()
+            .find(|call| call.method != "route_layer" && call.method != "layer")
+            .map(|call| byte_range(*call).end)
+            .unwrap_or_else(|| byte_range(first).end);
+        return Ok((end, format!(".{}({})", endpoint.method, handler)));
+    }
+
+    let end = calls
+        .iter()
+        .find(|call| call.method == "route")
+        .map(|call| byte_range(*call).end)
+        .unwrap_or_else(|| byte_range(base).end);
+    Ok((end, format!("\n        .route(\"{}\", {}({}))", endpoint.path, endpoint.method, handler)))
+}
+
+/// Add the handler to `paths(...)` of the module's `#[openapi]` struct, if it has one.
+fn register_openapi_path(mod_rs: &mut String, mod_path: &Path, endpoint: &Endpoint) -> Result<()> {
+    let api = format!("{}Api", capitalize(&endpoint.module));
+    let ast = parse_file(mod_path, mod_rs)?;
+    let attr = ast.items.iter().find_map(|item| match item {
+        Item::Struct(s) if s.ident == api => s.attrs.iter().find(|attr| attr.path().is_ident("openapi")),
+        _ => None,
+    });
+    let Some(Meta::List(list)) = attr.map(|attr| &attr.meta) else {
+        return Ok(());
+    };
+    let tokens: Vec<TokenTree> = list.tokens.clone().into_iter().collect();
+    let paths = tokens.windows(2).find_map(|pair| match pair {
+        [TokenTree::Ident(ident), TokenTree::Group(group)] if ident == "paths" => Some(group),
+        _ => None,
+    });
+    if let Some(paths) = paths {
+        let close = paths.span_close().byte_range().start;
+        let separator = if paths.stream().is_empty() { "" } else { ", " };
+        mod_rs.insert_str(close, &format!("{}{}_controller::{}", separator, endpoint.module, endpoint.action));
+    }
+    Ok(())
+}
+
+
+//===================================tests==========================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::generate_module::{generate_module, ModuleOptions};
+    use crate::utils::testing::in_temp_project;
+
+    fn options(action: &str, method: HttpMethod, path: &str) -> RouteOptions {
+        RouteOptions {
+            module: "hero".to_string(),
+            action: action.to_string(),
+            method,
+            path: Some(path.to_string()),
+            body: None,
+            query: None,
+        }
+    }
+
+    #[test]
+    fn test_generate_route_adds_handler_stub_and_route() {
+        in_temp_project("route", |root| {
+            fs::write(root.join("src/main.rs"), "use axum::Router;\n\nasync fn main() {\n    let app = Router::new();\n}\n").unwrap();
+            generate_module(ModuleOptions { name: "hero".to_string(), fields: vec![], permissions: false, version: None, id: None, timestamps: false, soft_delete: false }).unwrap();
+
+            let publish = RouteOptions { body: Some("PublishHeroDto".to_string()), ..options("publish", HttpMethod::Post, "/{id}/publish") };
+            generate_route(publish).unwrap();
+            generate_route(options("tag", HttpMethod::Put, "/{id}/tags/{tag}")).unwrap();
+            generate_route(options("purge", HttpMethod::Delete, "/")).unwrap();
+            assert!(generate_route(options("publish", HttpMethod::Get, "/published")).is_err());
+            assert!(generate_route(options("archive", HttpMethod::Post, "/{id}/publish")).is_err());
+
+            let hero = root.join("src/hero");
+            let files = ["mod.rs", "hero_controller.rs", "hero_service.rs", "dto/hero_dto.rs"].map(|file| {
+                let content = fs::read_to_string(hero.join(file)).unwrap();
+                syn::parse_file(&content).unwrap_or_else(|err| panic!("{} no longer parses: {}", file, err));
+                content
+            });
+            let [mod_rs, controller, service, dto] = files;
+
+            assert!(mod_rs.contains(".route(\"/\", post(hero_controller::create).get(hero_controller::find_all).delete(hero_controller::purge))"));
+            assert!(mod_rs.contains("\n        .route(\"/{id}/publish\", post(hero_controller::publish))"));
+            assert!(mod_rs.contains("use axum::routing::put;"));
+            assert!(controller.contains("pub async fn publish(Path(id): Path<u32>, Json(dto): Json<PublishHeroDto>) -> (StatusCode, String) {"));
+            assert!(controller.contains("pub async fn tag(Path((id, tag)): Path<(u32, String)>)"));
+            assert!(controller.contains("use crate::hero::dto::hero_dto::PublishHeroDto;"));
+            assert!(service.contains("pub async fn publish(id: u32, _dto: PublishHeroDto) -> String {"));
+            assert!(service.contains("\"purge hero: not implemented yet\".to_string()"));
+            assert_eq!(dto.matches("pub struct PublishHeroDto {").count(), 1);
+        });
+    }
+
+    #[test]
+    fn test_path_params_are_typed_like_ids() {
+        let params = path_params("/{id}/tags/{tag}/{owner_id}", "u32");
+        let names: Vec<(&str, &str)> = params.iter().map(|(n, t)| (n.as_str(), t.as_str())).collect();
+        assert_eq!(names, vec![("id", "u32"), ("tag", "String"), ("owner_id", "u32")]);
+    }
+}
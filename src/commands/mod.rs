@@ -2,3 +2,14 @@ pub mod new;
 pub mod generate_module;
 pub mod templates;
 pub mod generate_item;
+pub mod completions;
+pub mod schematics;
+pub mod wizard;
+pub mod presets;
+pub mod generate_config;
+pub mod generate_guard;
+pub mod generate_auth;
+pub mod generate_rbac;
+pub mod generate_route;
+pub mod generate_relation;
+pub mod version;
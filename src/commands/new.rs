@@ -2,37 +2,116 @@ use anyhow::Result;
 use std::process::Command;
 use std::path::Path;
 
-use super::templates::new_templates::main_template;
+use crate::utils::file::ensure_dir;
+use crate::utils::project::{AuthStrategy, ProjectConfig};
 
-pub fn create_new_project(name: String) -> Result<()> {
+use super::generate_auth::generate_auth;
+use super::templates::dependencies::dependencies;
+use super::presets::PresetFile;
+use super::templates::new_templates::{
+    cargo_toml_template,
+    config_template,
+    db_template,
+    default_config_toml_template,
+    error_template,
+    main_template,
+    production_config_toml_template,
+    shutdown_template,
+    telemetry_template,
+};
+
+/// Answers for `axumate new`, given as flags or collected by the wizard
+pub struct NewOptions {
+    pub name: String,
+    pub project: ProjectConfig,
+    /// Resolve the newest dependency versions with `cargo add` instead of the pinned set
+    pub latest: bool,
+    /// Extra files from the chosen preset
+    pub files: Vec<PresetFile>,
+}
+
+pub fn create_new_project(options: NewOptions) -> Result<()> {
+    let name = &options.name;
     println!("Creating new Cargo project: {}", name);
+    options.project.id_strategy().check(options.project.database)?;
 
-    // Step 1: Run `cargo new <name>`
+    // Step 1: Run `cargo new <name>` (local only, no network needed)
     let status = Command::new("cargo")
         .arg("new")
-        .arg(&name)
+        .arg(name)
         .status()?;
     if !status.success() {
         anyhow::bail!("Failed to create new Cargo project");
     }
 
-    let project_dir = Path::new(&name);
-
-    // Step 2: Add dependencies using `cargo add`
-    for dep in &["axum", "tokio@1 --features full", "once_cell", "serde --features derive"] {
-        let status = Command::new("cargo")
-            .current_dir(project_dir)
-            .arg("add")
-            .args(dep.split_whitespace())
-            .status()?;
-        if !status.success() {
-            anyhow::bail!("Failed to add dependency: {}", dep);
+    let project_dir = Path::new(name);
+
+    // Step 2: Add dependencies, pinned by default so this works offline and is reproducible
+    let deps = dependencies(&options.project);
+    if options.latest {
+        for dep in &deps {
+            let status = Command::new("cargo")
+                .current_dir(project_dir)
+                .arg("add")
+                .args(dep.cargo_add_args())
+                .status()?;
+            if !status.success() {
+                anyhow::bail!("Failed to add dependency: {}", dep.name);
+            }
         }
+    } else {
+        std::fs::write(project_dir.join("Cargo.toml"), cargo_toml_template(name, &deps))?;
     }
 
     // Step 3: Replace main.rs with our template
     let main_rs_path = project_dir.join("src/main.rs");
-    std::fs::write(main_rs_path, main_template(&name))?;
+    std::fs::write(main_rs_path, main_template(name, &options.project))?;
+
+    // Step 4: Supporting modules, published from lib.rs where generated modules are compiled too
+    std::fs::write(project_dir.join("src/config.rs"), config_template(name, &options.project))?;
+    let config_dir = ensure_dir(&project_dir.join("config"))?;
+    std::fs::write(config_dir.join("default.toml"), default_config_toml_template(name, &options.project))?;
+    std::fs::write(config_dir.join("production.toml"), production_config_toml_template(&options.project))?;
+    std::fs::write(project_dir.join("src/shutdown.rs"), shutdown_template(options.project.tracing))?;
+    let mut lib_mods = vec!["config", "shutdown"];
+    if options.project.tracing {
+        std::fs::write(project_dir.join("src/telemetry.rs"), telemetry_template())?;
+        lib_mods.push("telemetry");
+    }
+    if options.project.database.is_sql() {
+        // database wiring and the migrations/ dir sqlx::migrate!() reads at compile time
+        std::fs::write(project_dir.join("src/db.rs"), db_template(options.project.database))?;
+        let migrations = ensure_dir(&project_dir.join("migrations"))?;
+        std::fs::write(migrations.join(".gitkeep"), "")?;
+        lib_mods.push("db");
+    }
+    if options.project.error_handling {
+        std::fs::write(project_dir.join("src/error.rs"), error_template(options.project.database))?;
+        lib_mods.push("error");
+    }
+    let decls: String = lib_mods.iter().map(|m| format!("pub mod {};\n", m)).collect();
+    std::fs::write(project_dir.join("src/lib.rs"), format!("// auto-generated lib.rs\n\n{}", decls))?;
+
+    // Step 5: Extra files bundled with the preset
+    for file in &options.files {
+        let path = project_dir.join(&file.path);
+        if let Some(parent) = path.parent() {
+            ensure_dir(parent)?;
+        }
+        std::fs::write(&path, file.contents.replace("{{name}}", name))?;
+    }
+
+    // Step 6: Remember the choices for later `axumate generate` runs
+    options.project.save(project_dir)?;
+
+    // Step 7: The auth module is generated like `axumate generate auth` would, from inside the project
+    if options.project.auth != AuthStrategy::None {
+        let previous = std::env::current_dir()?;
+        std::env::set_current_dir(project_dir)?;
+        let result = generate_auth(options.project.auth);
+        std::env::set_current_dir(previous)?;
+        result?;
+    }
 
     println!("Axum project '{}' created successfully!", name);
     Ok(())
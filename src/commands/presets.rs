@@ -0,0 +1,121 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::utils::project::ProjectConfig;
+
+/// Presets shipped with axumate
+const BUILTIN_PRESETS: &[(&str, &str)] = &[
+    ("minimal", include_str!("templates/presets/minimal.toml")),
+    ("rest-api", include_str!("templates/presets/rest-api.toml")),
+    ("fullstack", include_str!("templates/presets/fullstack.toml")),
+    ("microservice", include_str!("templates/presets/microservice.toml")),
+];
+
+/// An extra file a preset adds to the project; `{{name}}` in the contents is replaced by the project name
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+pub struct PresetFile {
+    pub path: String,
+    pub contents: String,
+}
+
+/// A named bundle of project options and extra files for `axumate new --preset`
+#[derive(Clone, Debug, Deserialize)]
+pub struct Preset {
+    #[serde(skip)]
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub project: ProjectConfig,
+    #[serde(default)]
+    pub files: Vec<PresetFile>,
+}
+
+impl Preset {
+    fn parse(name: &str, content: &str) -> Result<Self> {
+        let mut preset: Preset = toml::from_str(content).with_context(|| format!("Invalid preset `{}`", name))?;
+        preset.name = name.to_string();
+        Ok(preset)
+    }
+}
+
+/// Directory for user presets: `$AXUMATE_HOME/presets`, or `~/.axumate/presets`.
+pub fn user_presets_dir() -> Option<PathBuf> {
+    let home = env::var_os("AXUMATE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").or_else(|| env::var_os("USERPROFILE")).map(|h| PathBuf::from(h).join(".axumate")))?;
+    Some(home.join("presets"))
+}
+
+/// Every available preset: built-ins first, then user presets from `user_presets_dir()`.
+/// A user preset with a built-in's name replaces it.
+pub fn list_presets() -> Result<Vec<Preset>> {
+    let mut presets = BUILTIN_PRESETS
+        .iter()
+        .map(|(name, content)| Preset::parse(name, content))
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut user_files: Vec<PathBuf> = user_presets_dir()
+        .and_then(|dir| fs::read_dir(dir).ok())
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "toml"))
+        .collect();
+    user_files.sort();
+
+    for path in user_files {
+        let name = path.file_stem().unwrap_or_default().to_string_lossy().into_owned();
+        let preset = Preset::parse(&name, &fs::read_to_string(&path)?)?;
+        match presets.iter_mut().find(|p| p.name == name) {
+            Some(existing) => *existing = preset,
+            None => presets.push(preset),
+        }
+    }
+    Ok(presets)
+}
+
+/// Look up a preset by name.
+pub fn load_preset(name: &str) -> Result<Preset> {
+    let presets = list_presets()?;
+    let names = presets.iter().map(|p| p.name.as_str()).collect::<Vec<_>>().join(", ");
+    presets
+        .iter()
+        .find(|p| p.name == name)
+        .cloned()
+        .with_context(|| format!("Unknown preset `{}`, expected one of: {}", name, names))
+}
+
+
+//===================================tests==========================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::project::{AuthStrategy, Database};
+
+    #[test]
+    fn test_builtin_presets_parse() {
+        let presets = list_presets().unwrap();
+        for (name, _) in BUILTIN_PRESETS {
+            assert!(presets.iter().any(|p| p.name == *name), "missing preset {}", name);
+        }
+
+        let fullstack = load_preset("fullstack").unwrap();
+        assert_eq!(fullstack.project.database, Database::Postgres);
+        assert!(fullstack.project.health);
+        assert_eq!(fullstack.project.auth, AuthStrategy::Jwt);
+        assert_eq!(fullstack.files[0].path, ".env.example");
+
+        let dockerfile = &load_preset("microservice").unwrap().files[0].contents;
+        assert!(dockerfile.contains("FROM rust:1-slim-bookworm AS build") && dockerfile.contains("FROM debian:bookworm-slim"));
+        assert!(dockerfile.contains("COPY --from=build /app/config ./config"));
+        assert!(dockerfile.contains("ENV APP_HOST=0.0.0.0"));
+
+        assert!(load_preset("nope").is_err());
+    }
+}
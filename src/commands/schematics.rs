@@ -0,0 +1,136 @@
+use anyhow::{Context, Result};
+use clap::Command;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::utils::common::capitalize;
+use crate::utils::file::ensure_dir;
+
+/// Project-local directory holding user-defined schematics, one sub-directory per schematic.
+pub const USER_TEMPLATES_DIR: &str = ".axumate/templates";
+
+/// Optional file inside a user schematic whose first line is shown by `generate --list`.
+const DESCRIPTION_FILE: &str = "description.txt";
+
+/// A schematic defined by the user under `.axumate/templates/{name}/`
+pub struct UserTemplate {
+    pub name: String,
+    pub description: String,
+    pub dir: PathBuf,
+}
+
+/// Find user-defined schematics in the current project, sorted by name.
+pub fn user_templates() -> Vec<UserTemplate> {
+    let mut templates: Vec<UserTemplate> = fs::read_dir(USER_TEMPLATES_DIR)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|entry| entry.path().is_dir())
+        .filter_map(|entry| {
+            let name = entry.file_name().to_str()?.to_string();
+            let description = fs::read_to_string(entry.path().join(DESCRIPTION_FILE))
+                .ok()
+                .and_then(|d| d.lines().next().map(|l| l.trim().to_string()))
+                .unwrap_or_else(|| "User-defined template".to_string());
+            Some(UserTemplate { name, description, dir: entry.path() })
+        })
+        .collect();
+    templates.sort_by(|a, b| a.name.cmp(&b.name));
+    templates
+}
+
+/// Print every schematic available to `generate`: the built-in ones from the CLI definition, then user-defined ones.
+pub fn print_schematics(generate: &Command) {
+    println!("{:<14} {:<8} Description", "Schematic", "Alias");
+
+    for sub in generate.get_subcommands().filter(|s| !s.is_hide_set()) {
+        let aliases = sub.get_visible_aliases().collect::<Vec<_>>().join(", ");
+        let about = sub.get_about().map(|a| a.to_string()).unwrap_or_default();
+        println!("{:<14} {:<8} {}", sub.get_name(), aliases, about);
+    }
+
+    for template in user_templates() {
+        println!("{:<14} {:<8} {} (user)", template.name, "", template.description);
+    }
+}
+
+/// Generate `name` from the user-defined schematic `schematic`.
+/// Every file under the schematic dir is copied into the project root; `__name__` in paths and
+/// `{{name}}` / `{{Name}}` in contents are replaced with the given name.
+pub fn generate_from_user_template(schematic: &str, name: &str) -> Result<()> {
+    let template = user_templates()
+        .into_iter()
+        .find(|t| t.name == schematic)
+        .with_context(|| {
+            format!("Unknown schematic `{}`. Run `axumate generate --list` to see what is available", schematic)
+        })?;
+
+    println!("Generating {}: {}", schematic, name);
+
+    let mut files = Vec::new();
+    collect_files(&template.dir, &mut files)?;
+
+    for file in files {
+        let relative = file.strip_prefix(&template.dir)?;
+        if relative == Path::new(DESCRIPTION_FILE) {
+            continue;
+        }
+
+        let target = PathBuf::from(relative.to_string_lossy().replace("__name__", name));
+        if let Some(parent) = target.parent() {
+            ensure_dir(parent)?;
+        }
+
+        let content = fs::read_to_string(&file)?
+            .replace("{{name}}", name)
+            .replace("{{Name}}", &capitalize(name));
+        fs::write(&target, content)?;
+
+        println!("Created {}", target.to_string_lossy());
+    }
+
+    println!("{} {} generated successfully!", capitalize(schematic), name);
+    Ok(())
+}
+
+/// Recursively collect every file below `dir`.
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_files(&path, files)?;
+        } else {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+
+//===================================tests==========================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::testing::in_temp_project;
+
+    #[test]
+    fn test_generate_from_user_template_renders_names() {
+        in_temp_project("user-template", |root| {
+            let dir = root.join(USER_TEMPLATES_DIR).join("repository/src/__name__");
+            fs::create_dir_all(&dir).unwrap();
+            fs::write(dir.join("__name___repository.rs"), "pub struct {{Name}}Repository;\n").unwrap();
+            fs::write(root.join(USER_TEMPLATES_DIR).join("repository").join(DESCRIPTION_FILE), "Generate a repository\n").unwrap();
+
+            let templates = user_templates();
+            assert_eq!(templates.len(), 1);
+            assert_eq!(templates[0].description, "Generate a repository");
+
+            generate_from_user_template("repository", "hero").unwrap();
+
+            let generated = fs::read_to_string(root.join("src/hero/hero_repository.rs")).unwrap();
+            assert_eq!(generated, "pub struct HeroRepository;\n");
+            assert!(!root.join(DESCRIPTION_FILE).exists());
+        });
+    }
+}
@@ -0,0 +1,740 @@
+use crate::utils::project::{AuthStrategy, Database, ProjectConfig};
+
+/// Type of user ids: the database's BIGINT for SQL projects, a plain counter otherwise
+fn id_type(project: &ProjectConfig) -> &'static str {
+    if project.database.is_sql() { "i64" } else { "u32" }
+}
+
+/// src/auth/mod.rs
+pub fn auth_mod_template(strategy: AuthStrategy) -> String {
+    let (mods, routes) = match strategy {
+        AuthStrategy::Session => (
+            "pub mod csrf;\npub mod current_user;\npub mod dto;\npub mod entities;\npub mod session_store;\n",
+            "        .route(\"/logout\", post(auth_controller::logout))\n",
+        ),
+        _ => (
+            "pub mod current_user;\npub mod dto;\npub mod entities;\n",
+            "        .route(\"/refresh\", post(auth_controller::refresh))\n",
+        ),
+    };
+    format!(
+        r#"pub mod auth_controller;
+pub mod auth_service;
+{mods}
+use axum::Router;
+use axum::routing::{{get, post}};
+
+pub fn routes() -> Router {{
+    auth_service::check_secret();
+    Router::new()
+        .route("/register", post(auth_controller::register))
+        .route("/login", post(auth_controller::login))
+{routes}        .route("/me", get(auth_controller::me))
+}}
+"#
+    )
+}
+
+/// src/auth/auth_controller.rs
+pub fn auth_controller_template(strategy: AuthStrategy) -> String {
+    match strategy {
+        AuthStrategy::Session => r#"use axum::http::{HeaderMap, StatusCode};
+use axum::Json;
+use axum_extra::extract::cookie::SignedCookieJar;
+
+use crate::auth::auth_service::{self, unauthorized, AuthResult};
+use crate::auth::dto::auth_dto::{LoginDto, RegisterDto, SessionResponse};
+
+/// POST /auth/register: create the user and sign them in
+pub async fn register(headers: HeaderMap, Json(dto): Json<RegisterDto>) -> AuthResult<(StatusCode, SignedCookieJar, Json<SessionResponse>)> {
+    let user = auth_service::register(dto).await?;
+    let (jar, session) = auth_service::start_session(&headers, &user).await?;
+    Ok((StatusCode::CREATED, jar, Json(SessionResponse::from(&session))))
+}
+
+/// POST /auth/login
+pub async fn login(headers: HeaderMap, Json(dto): Json<LoginDto>) -> AuthResult<(SignedCookieJar, Json<SessionResponse>)> {
+    let user = auth_service::login(dto).await?;
+    let (jar, session) = auth_service::start_session(&headers, &user).await?;
+    Ok((jar, Json(SessionResponse::from(&session))))
+}
+
+/// POST /auth/logout: drop the session and its cookie
+pub async fn logout(headers: HeaderMap) -> AuthResult<(SignedCookieJar, StatusCode)> {
+    let jar = auth_service::end_session(&headers).await?;
+    Ok((jar, StatusCode::NO_CONTENT))
+}
+
+/// GET /auth/me: the signed-in user and the CSRF token to send with unsafe requests
+pub async fn me(headers: HeaderMap) -> AuthResult<Json<SessionResponse>> {
+    let session = auth_service::current_session(&headers).await?.ok_or_else(|| unauthorized("not signed in"))?;
+    Ok(Json(SessionResponse::from(&session)))
+}
+"#
+        .to_string(),
+        _ => r#"use axum::http::StatusCode;
+use axum::Json;
+
+use crate::auth::auth_service::{self, AuthResult};
+use crate::auth::current_user::CurrentUser;
+use crate::auth::dto::auth_dto::{LoginDto, RefreshDto, RegisterDto, TokenResponse};
+
+/// POST /auth/register
+pub async fn register(Json(dto): Json<RegisterDto>) -> AuthResult<(StatusCode, Json<TokenResponse>)> {
+    let user = auth_service::register(dto).await?;
+    Ok((StatusCode::CREATED, Json(auth_service::issue_tokens(user.id, &user.email)?)))
+}
+
+/// POST /auth/login
+pub async fn login(Json(dto): Json<LoginDto>) -> AuthResult<Json<TokenResponse>> {
+    let user = auth_service::login(dto).await?;
+    Ok(Json(auth_service::issue_tokens(user.id, &user.email)?))
+}
+
+/// POST /auth/refresh: trade a refresh token for a new token pair
+pub async fn refresh(Json(dto): Json<RefreshDto>) -> AuthResult<Json<TokenResponse>> {
+    Ok(Json(auth_service::refresh(dto)?))
+}
+
+/// GET /auth/me
+pub async fn me(user: CurrentUser) -> Json<CurrentUser> {
+    Json(user)
+}
+"#
+        .to_string(),
+    }
+}
+
+/// Error type of the auth module: `AppError` when the project has one, a status and message otherwise
+fn auth_error_template(project: &ProjectConfig, strategy: AuthStrategy) -> String {
+    let (alias, unauthorized, forbidden, bad_request, internal) = if project.error_handling {
+        (
+            "/// Errors of the auth module; render as JSON through `AppError`\npub type AuthError = AppError;",
+            "AppError::Unauthorized(msg.to_string())",
+            "AppError::Forbidden(msg.to_string())",
+            "AppError::BadRequest(msg.to_string())",
+            "AppError::Internal(msg.to_string())",
+        )
+    } else {
+        (
+            "/// Errors of the auth module, rendered as a status and a plain-text message\npub type AuthError = (StatusCode, String);",
+            "(StatusCode::UNAUTHORIZED, msg.to_string())",
+            "(StatusCode::FORBIDDEN, msg.to_string())",
+            "(StatusCode::BAD_REQUEST, msg.to_string())",
+            "(StatusCode::INTERNAL_SERVER_ERROR, msg.to_string())",
+        )
+    };
+    // only sessions reject with 403, for a missing CSRF token
+    let forbidden = if strategy == AuthStrategy::Session {
+        format!("\npub fn forbidden(msg: &str) -> AuthError {{\n    {}\n}}\n", forbidden)
+    } else {
+        String::new()
+    };
+    format!(
+        r#"{alias}
+
+pub fn unauthorized(msg: &str) -> AuthError {{
+    {unauthorized}
+}}
+{forbidden}
+pub fn bad_request(msg: &str) -> AuthError {{
+    {bad_request}
+}}
+
+pub fn internal(msg: impl ToString) -> AuthError {{
+    {internal}
+}}
+"#
+    )
+}
+
+/// src/auth/auth_service.rs: users and password hashing, plus tokens or sessions depending on `strategy`
+pub fn auth_service_template(project: &ProjectConfig, strategy: AuthStrategy) -> String {
+    let error_use = if project.error_handling { "use crate::error::AppError;\n" } else { "use axum::http::StatusCode;\n" };
+    let errors = auth_error_template(project, strategy);
+    let secret = if strategy == AuthStrategy::Session { "session_secret" } else { "jwt_secret" };
+    let env = secret.to_uppercase();
+    let warn = if project.tracing { "tracing::warn!" } else { "eprintln!" };
+    let (store_uses, users) = if project.database.is_sql() {
+        ("use crate::db;\n", sql_users_template())
+    } else {
+        ("use once_cell::sync::Lazy;\nuse tokio::sync::RwLock;\n", in_memory_users_template())
+    };
+    let (strategy_uses, strategy_code) = match strategy {
+        AuthStrategy::Session => {
+            let lazy = if project.database.is_sql() { "use once_cell::sync::Lazy;\n" } else { "" };
+            (
+                format!(
+                    "use argon2::password_hash::rand_core::RngCore;\nuse axum::http::HeaderMap;\nuse axum_extra::extract::cookie::{{Cookie, Key, SameSite, SignedCookieJar}};\n{}use std::time::{{SystemTime, UNIX_EPOCH}};\n",
+                    lazy
+                ),
+                session_functions_template(),
+            )
+        }
+        _ => (
+            "use jsonwebtoken::{decode, encode, get_current_timestamp, DecodingKey, EncodingKey, Header, Validation};\nuse serde::{Deserialize, Serialize};\n".to_string(),
+            jwt_functions_template(project),
+        ),
+    };
+    let dto_use = match strategy {
+        AuthStrategy::Session => "use crate::auth::dto::auth_dto::{LoginDto, RegisterDto};\nuse crate::auth::entities::session_entity::Session;\nuse crate::auth::session_store;\n",
+        _ => "use crate::auth::dto::auth_dto::{LoginDto, RefreshDto, RegisterDto, TokenResponse};\n",
+    };
+
+    format!(
+        r#"use argon2::password_hash::rand_core::OsRng;
+use argon2::password_hash::{{PasswordHash, PasswordHasher, PasswordVerifier, SaltString}};
+use argon2::Argon2;
+{strategy_uses}{store_uses}{error_use}
+{dto_use}use crate::auth::entities::user_entity::User;
+use crate::config::{{AppConfig, CONFIG}};
+
+{errors}
+pub type AuthResult<T> = Result<T, AuthError>;
+{users}{strategy_code}
+fn check_credentials(dto: &RegisterDto) -> AuthResult<()> {{
+    if dto.email.trim().is_empty() || dto.password.len() < 8 {{
+        return Err(bad_request("an email and a password of at least 8 characters are required"));
+    }}
+    Ok(())
+}}
+
+/// Argon2 is deliberately slow, so it runs on the blocking pool instead of stalling the async workers
+async fn hash_password(password: &str) -> AuthResult<String> {{
+    let password = password.to_owned();
+    tokio::task::spawn_blocking(move || {{
+        let salt = SaltString::generate(&mut OsRng);
+        let hash = Argon2::default().hash_password(password.as_bytes(), &salt).map_err(internal)?;
+        Ok(hash.to_string())
+    }})
+    .await
+    .map_err(internal)?
+}}
+
+async fn verify_password(password: &str, hash: &str) -> AuthResult<()> {{
+    let (password, hash) = (password.to_owned(), hash.to_owned());
+    tokio::task::spawn_blocking(move || {{
+        let parsed = PasswordHash::new(&hash).map_err(internal)?;
+        Argon2::default()
+            .verify_password(password.as_bytes(), &parsed)
+            .map_err(|_| unauthorized("invalid email or password"))
+    }})
+    .await
+    .map_err(internal)?
+}}
+
+/// Argon2 hash, with the default parameters, of a password no user has
+const DUMMY_HASH: &str = "$argon2id$v=19$m=19456,t=2,p=1$YXh1bWF0ZWR1bW15c2FsdA$TtjsckKQD8wjQEkwYSZsQAHD3AeT3MeEhiIGCUnh3eg";
+
+/// Hash the password anyway when the email is unknown, so the response takes
+/// as long as for a wrong password and does not tell which emails are registered
+async fn reject_unknown_email(password: &str) -> AuthError {{
+    let _ = verify_password(password, DUMMY_HASH).await;
+    unauthorized("invalid email or password")
+}}
+
+/// Refuse to run a release build with the placeholder `{secret}` from config.rs, and warn in debug builds.
+pub fn check_secret() {{
+    if CONFIG.{secret} != AppConfig::default().{secret} {{
+        return;
+    }}
+    if cfg!(not(debug_assertions)) {{
+        panic!("`{secret}` is still the placeholder; set APP_{env} or `{secret}` in config/*.toml");
+    }}
+    {warn}("`{secret}` is the placeholder from config.rs; set APP_{env} before deploying");
+}}
+"#
+    )
+}
+
+/// Access/refresh token issuing and verification
+fn jwt_functions_template(project: &ProjectConfig) -> String {
+    format!(
+        r#"
+/// What a token is good for; refresh tokens only buy new token pairs
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TokenKind {{
+    Access,
+    Refresh,
+}}
+
+/// JWT payload
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Claims {{
+    /// User id
+    pub sub: {id},
+    pub email: String,
+    pub kind: TokenKind,
+    /// Expiry, in seconds since the Unix epoch
+    pub exp: u64,
+}}
+
+pub fn refresh(dto: RefreshDto) -> AuthResult<TokenResponse> {{
+    let claims = verify_token(&dto.refresh_token, TokenKind::Refresh)?;
+    issue_tokens(claims.sub, &claims.email)
+}}
+
+/// Decode `token` and check its signature, expiry and kind.
+pub fn verify_token(token: &str, kind: TokenKind) -> AuthResult<Claims> {{
+    let key = DecodingKey::from_secret(CONFIG.jwt_secret.as_bytes());
+    let data = decode::<Claims>(token, &key, &Validation::default()).map_err(|_| unauthorized("invalid or expired token"))?;
+    if data.claims.kind != kind {{
+        return Err(unauthorized("wrong kind of token"));
+    }}
+    Ok(data.claims)
+}}
+
+pub fn issue_tokens(id: {id}, email: &str) -> AuthResult<TokenResponse> {{
+    Ok(TokenResponse {{
+        access_token: sign(id, email, TokenKind::Access, CONFIG.jwt_access_ttl_secs)?,
+        refresh_token: sign(id, email, TokenKind::Refresh, CONFIG.jwt_refresh_ttl_secs)?,
+        token_type: "Bearer".to_string(),
+        expires_in: CONFIG.jwt_access_ttl_secs,
+    }})
+}}
+
+fn sign(id: {id}, email: &str, kind: TokenKind, ttl_secs: u64) -> AuthResult<String> {{
+    let claims = Claims {{ sub: id, email: email.to_string(), kind, exp: get_current_timestamp() + ttl_secs }};
+    encode(&Header::default(), &claims, &EncodingKey::from_secret(CONFIG.jwt_secret.as_bytes())).map_err(internal)
+}}
+"#,
+        id = id_type(project)
+    )
+}
+
+/// Signed session cookies backed by `session_store`
+fn session_functions_template() -> String {
+    r#"
+/// Name of the signed cookie holding the session id
+pub const SESSION_COOKIE: &str = "session";
+/// Header unsafe requests must echo the session's CSRF token in
+pub const CSRF_HEADER: &str = "x-csrf-token";
+
+/// Signing key for the session cookie, derived from `session_secret`
+static SESSION_KEY: Lazy<Key> = Lazy::new(|| Key::derive_from(CONFIG.session_secret.as_bytes()));
+
+/// Store a new session for `user` and set its cookie.
+pub async fn start_session(headers: &HeaderMap, user: &User) -> AuthResult<(SignedCookieJar, Session)> {
+    let session = Session {
+        id: random_token(),
+        user_id: user.id,
+        email: user.email.clone(),
+        csrf_token: random_token(),
+        expires_at: now() + CONFIG.session_ttl_secs as i64,
+    };
+    session_store::insert(&session).await?;
+
+    let cookie = Cookie::build((SESSION_COOKIE, session.id.clone()))
+        .path("/")
+        .http_only(true)
+        .same_site(SameSite::Lax)
+        // browsers drop Secure cookies over plain http, which is what debug builds usually serve
+        .secure(!cfg!(debug_assertions));
+    let jar = SignedCookieJar::from_headers(headers, SESSION_KEY.clone()).add(cookie);
+    Ok((jar, session))
+}
+
+/// The session named by the request's cookie, unless it is missing, tampered with or expired.
+pub async fn current_session(headers: &HeaderMap) -> AuthResult<Option<Session>> {
+    let jar = SignedCookieJar::from_headers(headers, SESSION_KEY.clone());
+    match jar.get(SESSION_COOKIE) {
+        Some(cookie) => session_store::find(cookie.value(), now()).await,
+        None => Ok(None),
+    }
+}
+
+/// Forget the request's session and remove its cookie.
+pub async fn end_session(headers: &HeaderMap) -> AuthResult<SignedCookieJar> {
+    let jar = SignedCookieJar::from_headers(headers, SESSION_KEY.clone());
+    if let Some(cookie) = jar.get(SESSION_COOKIE) {
+        session_store::remove(cookie.value()).await?;
+    }
+    Ok(jar.remove(Cookie::build(SESSION_COOKIE).path("/")))
+}
+
+/// 32 random bytes, hex-encoded
+fn random_token() -> String {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Seconds since the Unix epoch
+fn now() -> i64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs() as i64).unwrap_or_default()
+}
+"#
+    .to_string()
+}
+
+/// register/login against an in-memory user list
+fn in_memory_users_template() -> &'static str {
+    r#"
+static USERS: Lazy<RwLock<Vec<User>>> = Lazy::new(|| RwLock::new(Vec::new()));
+
+pub async fn register(dto: RegisterDto) -> AuthResult<User> {
+    check_credentials(&dto)?;
+    let password_hash = hash_password(&dto.password).await?;
+
+    let mut users = USERS.write().await;
+    if users.iter().any(|user| user.email == dto.email) {
+        return Err(bad_request("email is already registered"));
+    }
+    let id = users.iter().map(|user| user.id).max().unwrap_or(0) + 1;
+    let user = User { id, email: dto.email, password_hash };
+    users.push(user.clone());
+    Ok(user)
+}
+
+/// The user with these credentials
+pub async fn login(dto: LoginDto) -> AuthResult<User> {
+    let user = USERS.read().await.iter().find(|user| user.email == dto.email).cloned();
+    let Some(user) = user else {
+        return Err(reject_unknown_email(&dto.password).await);
+    };
+    verify_password(&dto.password, &user.password_hash).await?;
+    Ok(user)
+}
+"#
+}
+
+/// register/login against the `users` table
+fn sql_users_template() -> &'static str {
+    r#"
+pub async fn register(dto: RegisterDto) -> AuthResult<User> {
+    check_credentials(&dto)?;
+    let password_hash = hash_password(&dto.password).await?;
+
+    let id = sqlx::query_scalar::<_, i64>("INSERT INTO users (email, password_hash) VALUES ($1, $2) RETURNING id")
+        .bind(&dto.email)
+        .bind(&password_hash)
+        .fetch_one(db::pool())
+        .await
+        .map_err(|err| match err {
+            sqlx::Error::Database(db_err) if db_err.is_unique_violation() => bad_request("email is already registered"),
+            other => internal(other),
+        })?;
+
+    Ok(User { id, email: dto.email, password_hash })
+}
+
+/// The user with these credentials
+pub async fn login(dto: LoginDto) -> AuthResult<User> {
+    let user = sqlx::query_as::<_, User>("SELECT id, email, password_hash FROM users WHERE email = $1")
+        .bind(&dto.email)
+        .fetch_optional(db::pool())
+        .await
+        .map_err(internal)?;
+    let Some(user) = user else {
+        return Err(reject_unknown_email(&dto.password).await);
+    };
+    verify_password(&dto.password, &user.password_hash).await?;
+    Ok(user)
+}
+"#
+}
+
+/// src/auth/session_store.rs: where sessions live between requests
+pub fn session_store_template(project: &ProjectConfig) -> String {
+    if project.database.is_sql() {
+        r#"use crate::auth::auth_service::{internal, AuthResult};
+use crate::auth::entities::session_entity::Session;
+use crate::db;
+
+/// Sessions in the `sessions` table, so they survive restarts and are shared between instances
+pub async fn insert(session: &Session) -> AuthResult<()> {
+    sqlx::query("INSERT INTO sessions (id, user_id, email, csrf_token, expires_at) VALUES ($1, $2, $3, $4, $5)")
+        .bind(&session.id)
+        .bind(session.user_id)
+        .bind(&session.email)
+        .bind(&session.csrf_token)
+        .bind(session.expires_at)
+        .execute(db::pool())
+        .await
+        .map_err(internal)?;
+    Ok(())
+}
+
+/// The session `id`, if it has not expired by `now`
+pub async fn find(id: &str, now: i64) -> AuthResult<Option<Session>> {
+    sqlx::query_as::<_, Session>(
+        "SELECT id, user_id, email, csrf_token, expires_at FROM sessions WHERE id = $1 AND expires_at > $2",
+    )
+    .bind(id)
+    .bind(now)
+    .fetch_optional(db::pool())
+    .await
+    .map_err(internal)
+}
+
+pub async fn remove(id: &str) -> AuthResult<()> {
+    sqlx::query("DELETE FROM sessions WHERE id = $1").bind(id).execute(db::pool()).await.map_err(internal)?;
+    Ok(())
+}
+"#
+        .to_string()
+    } else {
+        r#"use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use tokio::sync::RwLock;
+
+use crate::auth::auth_service::AuthResult;
+use crate::auth::entities::session_entity::Session;
+
+/// Sessions of this process, lost on restart; async like the SQL-backed store it can be swapped for
+static SESSIONS: Lazy<RwLock<HashMap<String, Session>>> = Lazy::new(|| RwLock::new(HashMap::new()));
+
+pub async fn insert(session: &Session) -> AuthResult<()> {
+    SESSIONS.write().await.insert(session.id.clone(), session.clone());
+    Ok(())
+}
+
+/// The session `id`, if it has not expired by `now`
+pub async fn find(id: &str, now: i64) -> AuthResult<Option<Session>> {
+    let mut sessions = SESSIONS.write().await;
+    match sessions.get(id) {
+        Some(session) if session.expires_at > now => Ok(Some(session.clone())),
+        Some(_) => {
+            sessions.remove(id);
+            Ok(None)
+        }
+        None => Ok(None),
+    }
+}
+
+pub async fn remove(id: &str) -> AuthResult<()> {
+    SESSIONS.write().await.remove(id);
+    Ok(())
+}
+"#
+        .to_string()
+    }
+}
+
+/// src/auth/csrf.rs: the CSRF check main.rs layers over the whole app
+pub fn csrf_template() -> String {
+    r#"use axum::extract::Request;
+use axum::http::Method;
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+
+use crate::auth::auth_service::{self, forbidden, CSRF_HEADER};
+
+/// Reject unsafe requests (POST, PUT, PATCH, DELETE...) made with a session cookie
+/// unless they echo the session's CSRF token in the `x-csrf-token` header.
+/// Requests without a session carry no ambient credentials, so they pass.
+pub async fn csrf_protection(request: Request, next: Next) -> Response {
+    if matches!(*request.method(), Method::GET | Method::HEAD | Method::OPTIONS | Method::TRACE) {
+        return next.run(request).await;
+    }
+
+    let session = match auth_service::current_session(request.headers()).await {
+        Ok(session) => session,
+        Err(err) => return err.into_response(),
+    };
+    if let Some(session) = session {
+        let sent = request.headers().get(CSRF_HEADER).and_then(|value| value.to_str().ok()).unwrap_or_default();
+        if !constant_time_eq(sent.as_bytes(), session.csrf_token.as_bytes()) {
+            return forbidden("missing or invalid CSRF token").into_response();
+        }
+    }
+    next.run(request).await
+}
+
+/// Compare without returning early, so timing does not leak how much of the token matched
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+"#
+    .to_string()
+}
+
+/// src/auth/dto/auth_dto.rs
+pub fn auth_dto_template(project: &ProjectConfig, strategy: AuthStrategy) -> String {
+    let credentials = r#"use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize)]
+pub struct RegisterDto {
+    pub email: String,
+    pub password: String,
+}
+
+#[derive(Deserialize)]
+pub struct LoginDto {
+    pub email: String,
+    pub password: String,
+}
+"#;
+    let responses = match strategy {
+        AuthStrategy::Session => format!(
+            r#"
+/// The signed-in user and the token unsafe requests must send in `x-csrf-token`
+#[derive(Serialize)]
+pub struct SessionResponse {{
+    pub user_id: {id},
+    pub email: String,
+    pub csrf_token: String,
+}}
+
+impl From<&crate::auth::entities::session_entity::Session> for SessionResponse {{
+    fn from(session: &crate::auth::entities::session_entity::Session) -> Self {{
+        SessionResponse {{ user_id: session.user_id, email: session.email.clone(), csrf_token: session.csrf_token.clone() }}
+    }}
+}}
+"#,
+            id = id_type(project)
+        ),
+        _ => r#"
+#[derive(Deserialize)]
+pub struct RefreshDto {
+    pub refresh_token: String,
+}
+
+#[derive(Serialize)]
+pub struct TokenResponse {
+    pub access_token: String,
+    pub refresh_token: String,
+    pub token_type: String,
+    /// Lifetime of the access token in seconds
+    pub expires_in: u64,
+}
+"#
+        .to_string(),
+    };
+    format!("{}{}", credentials, responses)
+}
+
+/// src/auth/entities/user_entity.rs; never serialized, so the hash cannot leak into a response
+pub fn user_entity_template(project: &ProjectConfig) -> String {
+    let derives = if project.database.is_sql() { "Clone, Debug, sqlx::FromRow" } else { "Clone, Debug" };
+    format!(
+        r#"#[derive({derives})]
+pub struct User {{
+    pub id: {id},
+    pub email: String,
+    /// Argon2 PHC string
+    pub password_hash: String,
+}}
+"#,
+        id = id_type(project)
+    )
+}
+
+/// src/auth/entities/session_entity.rs
+pub fn session_entity_template(project: &ProjectConfig) -> String {
+    let derives = if project.database.is_sql() { "Clone, Debug, sqlx::FromRow" } else { "Clone, Debug" };
+    format!(
+        r#"#[derive({derives})]
+pub struct Session {{
+    /// Random id, stored in the signed session cookie
+    pub id: String,
+    pub user_id: {id},
+    pub email: String,
+    pub csrf_token: String,
+    /// Seconds since the Unix epoch
+    pub expires_at: i64,
+}}
+"#,
+        id = id_type(project)
+    )
+}
+
+/// src/auth/current_user.rs: the extractor handlers use to get the caller
+pub fn current_user_template(project: &ProjectConfig, strategy: AuthStrategy) -> String {
+    let (uses, source, body) = match strategy {
+        AuthStrategy::Session => (
+            "use crate::auth::auth_service::{self, unauthorized, AuthError};",
+            "the signed session cookie",
+            r#"        let session = auth_service::current_session(&parts.headers).await?.ok_or_else(|| unauthorized("not signed in"))?;
+        Ok(CurrentUser { id: session.user_id, email: session.email })"#,
+        ),
+        _ => (
+            "use axum::http::header;\nuse crate::auth::auth_service::{self, unauthorized, AuthError, TokenKind};",
+            "an `Authorization: Bearer <access token>` header",
+            r#"        let token = parts
+            .headers
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .ok_or_else(|| unauthorized("missing bearer token"))?;
+        let claims = auth_service::verify_token(token, TokenKind::Access)?;
+        Ok(CurrentUser { id: claims.sub, email: claims.email })"#,
+        ),
+    };
+    format!(
+        r#"use axum::extract::FromRequestParts;
+use axum::http::request::Parts;
+use serde::Serialize;
+
+{uses}
+
+/// The authenticated caller, read from {source}.
+/// Take it as a handler argument; requests that are not signed in get 401.
+#[derive(Clone, Debug, Serialize)]
+#[allow(dead_code)]
+pub struct CurrentUser {{
+    pub id: {id},
+    pub email: String,
+}}
+
+impl<S: Send + Sync> FromRequestParts<S> for CurrentUser {{
+    type Rejection = AuthError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {{
+{body}
+    }}
+}}
+"#,
+        id = id_type(project)
+    )
+}
+
+/// src/guards/auth.rs: a guard for handlers that need a signed-in caller but not its details
+pub fn auth_guard_template() -> String {
+    r#"use axum::extract::FromRequestParts;
+use axum::http::request::Parts;
+
+use crate::auth::auth_service::AuthError;
+use crate::auth::current_user::CurrentUser;
+
+/// Guards a handler: add `_guard: AuthGuard` to its parameters.
+/// Rejects with 401 unless the caller is signed in.
+#[allow(dead_code)]
+pub struct AuthGuard;
+
+impl<S: Send + Sync> FromRequestParts<S> for AuthGuard {
+    type Rejection = AuthError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        CurrentUser::from_request_parts(parts, state).await?;
+        Ok(AuthGuard)
+    }
+}
+"#
+    .to_string()
+}
+
+/// migrations/{version}_create_users.sql
+pub fn users_migration_template(database: Database) -> String {
+    let id_column = match database {
+        Database::Postgres => "id BIGSERIAL PRIMARY KEY",
+        _ => "id INTEGER PRIMARY KEY AUTOINCREMENT",
+    };
+    format!(
+        "CREATE TABLE IF NOT EXISTS users (\n    {},\n    email TEXT NOT NULL UNIQUE,\n    password_hash TEXT NOT NULL\n);\n",
+        id_column
+    )
+}
+
+/// migrations/{version}_create_sessions.sql
+pub fn sessions_migration_template(database: Database) -> String {
+    let integer = match database {
+        Database::Postgres => "BIGINT",
+        _ => "INTEGER",
+    };
+    format!(
+        "CREATE TABLE IF NOT EXISTS sessions (\n    id TEXT PRIMARY KEY,\n    user_id {0} NOT NULL REFERENCES users (id) ON DELETE CASCADE,\n    email TEXT NOT NULL,\n    csrf_token TEXT NOT NULL,\n    expires_at {0} NOT NULL\n);\n",
+        integer
+    )
+}
@@ -0,0 +1,255 @@
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::Path;
+
+use crate::utils::project::{AuthStrategy, Database, IdStrategy, ProjectConfig};
+
+/// A crate added to generated projects.
+/// Versions are pinned to the set axumate's templates are tested against, so `axumate new` is reproducible and works offline.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Dependency {
+    pub name: &'static str,
+    pub version: &'static str,
+    pub features: Vec<&'static str>,
+    pub default_features: bool,
+}
+
+impl Dependency {
+    fn new(name: &'static str, version: &'static str, features: &[&'static str]) -> Self {
+        Dependency { name, version, features: features.to_vec(), default_features: true }
+    }
+
+    fn without_default_features(mut self) -> Self {
+        self.default_features = false;
+        self
+    }
+
+    /// The line for the `[dependencies]` table of Cargo.toml
+    pub fn toml_line(&self) -> String {
+        dependency_line(self.name, self.version, self.default_features, &self.features)
+    }
+
+    /// Arguments for `cargo add`, leaving the version to cargo (used by `--latest`)
+    pub fn cargo_add_args(&self) -> Vec<String> {
+        let mut args = vec![self.name.to_string()];
+        if !self.default_features {
+            args.push("--no-default-features".to_string());
+        }
+        if !self.features.is_empty() {
+            args.push("--features".to_string());
+            args.push(self.features.join(","));
+        }
+        args
+    }
+}
+
+fn dependency_line(name: &str, version: &str, default_features: bool, features: &[&str]) -> String {
+    if features.is_empty() && default_features {
+        return format!("{} = \"{}\"", name, version);
+    }
+
+    let mut parts = vec![format!("version = \"{}\"", version)];
+    if !default_features {
+        parts.push("default-features = false".to_string());
+    }
+    if !features.is_empty() {
+        let features = features.iter().map(|f| format!("\"{}\"", f)).collect::<Vec<_>>().join(", ");
+        parts.push(format!("features = [{}]", features));
+    }
+    format!("{} = {{ {} }}", name, parts.join(", "))
+}
+
+/// tower-http with the given features, for the generated middlewares
+pub fn tower_http(features: &[&'static str]) -> Dependency {
+    Dependency::new("tower-http", "0.6.11", features)
+}
+
+/// tower, for the `Layer`/`Service` traits of generated layers
+pub fn tower() -> Dependency {
+    Dependency::new("tower", "0.5.3", &[])
+}
+
+/// sha2, for the key digests of api-key middlewares
+pub fn sha2() -> Dependency {
+    Dependency::new("sha2", "0.10.9", &[])
+}
+
+/// Crates, and features of crates, that modules with `id` ids need
+pub fn id_dependencies(id: IdStrategy, project: &ProjectConfig) -> Vec<Dependency> {
+    let mut deps = match id {
+        IdStrategy::U32 | IdStrategy::I64 => return vec![],
+        IdStrategy::Uuid => vec![Dependency::new("uuid", "1.28.0", &["v7", "serde"])],
+        IdStrategy::Ulid => vec![Dependency::new("ulid", "1.2.1", &["serde"])],
+    };
+    if project.database.is_sql() {
+        deps.push(Dependency::new("sqlx", "0.9.0", &["uuid"]));
+    }
+    if project.openapi {
+        deps.push(Dependency::new("utoipa", "5.5.0", &[if id == IdStrategy::Uuid { "uuid" } else { "ulid" }]));
+    }
+    deps
+}
+
+/// Crates, and features of crates, that `created_at`/`updated_at`/`deleted_at` fields need
+pub fn timestamp_dependencies(project: &ProjectConfig) -> Vec<Dependency> {
+    let mut deps = vec![Dependency::new("chrono", "0.4.45", &["serde"])];
+    if project.database.is_sql() {
+        deps.push(Dependency::new("sqlx", "0.9.0", &["chrono"]));
+    }
+    if project.openapi {
+        deps.push(Dependency::new("utoipa", "5.5.0", &["chrono"]));
+    }
+    deps
+}
+
+/// Crates the generated auth module needs for `strategy`
+pub fn auth_dependencies(strategy: AuthStrategy) -> Vec<Dependency> {
+    match strategy {
+        AuthStrategy::None => vec![],
+        AuthStrategy::Jwt => vec![
+            Dependency::new("argon2", "0.5.3", &[]),
+            Dependency::new("jsonwebtoken", "10.4.0", &["rust_crypto"]),
+        ],
+        AuthStrategy::Session => vec![
+            Dependency::new("argon2", "0.5.3", &[]),
+            Dependency::new("axum-extra", "0.12.6", &["cookie-signed", "cookie-key-expansion"]),
+        ],
+    }
+}
+
+/// Add `dep` to the `[dependencies]` of ./Cargo.toml, or merge its features into the existing entry.
+pub fn ensure_dependency(dep: &Dependency) -> Result<()> {
+    let path = Path::new("Cargo.toml");
+    if !path.exists() {
+        println!("No Cargo.toml found, add `{}` yourself", dep.toml_line());
+        return Ok(());
+    }
+    let mut content = fs::read_to_string(path)?;
+    let manifest: toml::Table = content.parse().context("Invalid Cargo.toml")?;
+
+    let Some(existing) = manifest.get("dependencies").and_then(|deps| deps.get(dep.name)) else {
+        // append to the end of the [dependencies] table, creating it if needed
+        let Some(header) = content.find("[dependencies]") else {
+            content.push_str(&format!("\n[dependencies]\n{}\n", dep.toml_line()));
+            return Ok(fs::write(path, content)?);
+        };
+        let table_end = content[header..].find("\n[").map(|pos| header + pos + 1).unwrap_or(content.len());
+        let insert_pos = content[..table_end].trim_end().len();
+        content.insert_str(insert_pos, &format!("\n{}", dep.toml_line()));
+        return Ok(fs::write(path, content)?);
+    };
+
+    let features: Vec<&str> = existing
+        .get("features")
+        .and_then(|features| features.as_array())
+        .map(|features| features.iter().filter_map(|f| f.as_str()).collect())
+        .unwrap_or_default();
+    let missing: Vec<&str> = dep.features.iter().copied().filter(|f| !features.contains(f)).collect();
+    if missing.is_empty() {
+        return Ok(());
+    }
+
+    // rewrite the single-line entry axumate writes, keeping its version and default-features
+    let version = existing.as_str().or_else(|| existing.get("version").and_then(|v| v.as_str())).unwrap_or(dep.version);
+    let default_features = existing.get("default-features").and_then(|v| v.as_bool()).unwrap_or(true);
+    let merged: Vec<&str> = features.iter().copied().chain(missing).collect();
+    let line = dependency_line(dep.name, version, default_features, &merged);
+
+    let prefix = format!("{} = ", dep.name);
+    let Some(start) = content.lines().position(|l| l.starts_with(&prefix)) else {
+        anyhow::bail!("Could not update `{}` in Cargo.toml, set it to: {}", dep.name, line);
+    };
+    let mut lines: Vec<&str> = content.lines().collect();
+    lines[start] = &line;
+    fs::write(path, lines.join("\n") + "\n")?;
+    Ok(())
+}
+
+/// Dependencies needed by the generated code for the chosen options
+pub fn dependencies(project: &ProjectConfig) -> Vec<Dependency> {
+    let mut deps = vec![
+        Dependency::new("axum", "0.8.9", &[]),
+        Dependency::new("config", "0.15.27", &["toml"]).without_default_features(),
+        Dependency::new("once_cell", "1.21.4", &[]),
+        Dependency::new("serde", "1.0.229", &["derive"]),
+        Dependency::new("tokio", "1.53.2", &["full"]),
+    ];
+    match project.database {
+        Database::None => {}
+        Database::Postgres => deps.push(Dependency::new("sqlx", "0.9.0", &["runtime-tokio", "postgres"])),
+        Database::Sqlite => deps.push(Dependency::new("sqlx", "0.9.0", &["runtime-tokio", "sqlite"])),
+    }
+    if project.openapi {
+        deps.push(Dependency::new("utoipa", "5.5.0", &[]));
+        deps.push(Dependency::new("utoipa-scalar", "0.3.0", &["axum"]));
+    }
+    if project.tracing {
+        deps.push(Dependency::new("tracing", "0.1.44", &[]));
+        deps.push(Dependency::new("tracing-subscriber", "0.3.23", &["env-filter", "json"]));
+        deps.push(tower_http(&["trace"]));
+    }
+    deps.extend(auth_dependencies(project.auth));
+    for dep in id_dependencies(project.id_strategy(), project) {
+        match deps.iter_mut().find(|existing| existing.name == dep.name) {
+            Some(existing) => existing.features.extend(dep.features),
+            None => deps.push(dep),
+        }
+    }
+    deps
+}
+
+
+//===================================tests==========================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::testing::in_temp_project;
+
+    #[test]
+    fn test_toml_line_formats_features() {
+        let sqlx = Dependency::new("sqlx", "0.9.0", &["runtime-tokio", "sqlite"]);
+        assert_eq!(sqlx.toml_line(), r#"sqlx = { version = "0.9.0", features = ["runtime-tokio", "sqlite"] }"#);
+        assert_eq!(sqlx.cargo_add_args(), vec!["sqlx", "--features", "runtime-tokio,sqlite"]);
+
+        let config = Dependency::new("config", "0.15.27", &["toml"]).without_default_features();
+        assert_eq!(config.toml_line(), r#"config = { version = "0.15.27", default-features = false, features = ["toml"] }"#);
+    }
+
+    #[test]
+    fn test_ensure_dependency_adds_and_merges_features() {
+        in_temp_project("ensure-dependency", |root| {
+            let manifest = root.join("Cargo.toml");
+            fs::write(&manifest, "[package]\nname = \"app\"\n\n[dependencies]\naxum = \"0.8.9\"\n").unwrap();
+
+            ensure_dependency(&tower_http(&["cors"])).unwrap();
+            ensure_dependency(&tower_http(&["timeout"])).unwrap();
+            ensure_dependency(&tower_http(&["cors"])).unwrap();
+
+            let content = fs::read_to_string(&manifest).unwrap();
+            assert!(content.ends_with(
+                "axum = \"0.8.9\"\ntower-http = { version = \"0.6.11\", features = [\"cors\", \"timeout\"] }\n"
+            ));
+        });
+    }
+
+    #[test]
+    fn test_dependencies_follow_project_options() {
+        let names = |project: &ProjectConfig| dependencies(project).iter().map(|d| d.name).collect::<Vec<_>>();
+
+        assert_eq!(names(&ProjectConfig::default()), vec!["axum", "config", "once_cell", "serde", "tokio"]);
+
+        let full = ProjectConfig { database: Database::Postgres, openapi: true, tracing: true, ..ProjectConfig::default() };
+        let full_names = names(&full);
+        for expected in ["sqlx", "utoipa", "utoipa-scalar", "tracing", "tracing-subscriber"] {
+            assert!(full_names.contains(&expected), "missing {}", expected);
+        }
+
+        let uuid = ProjectConfig { id: Some(IdStrategy::Uuid), ..full };
+        let deps = dependencies(&uuid);
+        let sqlx = deps.iter().find(|d| d.name == "sqlx").unwrap();
+        assert_eq!(sqlx.features, vec!["runtime-tokio", "postgres", "uuid"]);
+        assert!(deps.iter().any(|d| d.name == "uuid"));
+        assert_eq!(deps.iter().filter(|d| d.name == "utoipa").count(), 1);
+    }
+}
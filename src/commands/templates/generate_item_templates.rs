@@ -1,9 +1,29 @@
 use crate::utils::common::{capitalize};
+use crate::utils::field::Field;
+
+use super::generate_module_templates::timestamp_fields_template;
+
+/// Render `fields` as struct members (wrapped in `Option` when `optional`), or `placeholder` when there are none.
+fn struct_fields(fields: &[Field], optional: bool, placeholder: &str) -> String {
+    if fields.is_empty() {
+        return format!("    {}\n", placeholder);
+    }
+    fields
+        .iter()
+        .map(|f| {
+            if optional {
+                format!("    pub {}: Option<{}>,\n", f.name, f.ty)
+            } else {
+                format!("    pub {}: {},\n", f.name, f.ty)
+            }
+        })
+        .collect()
+}
 
 
 
 //-----------------------------------------controller--------------------------------------------
-pub fn simple_controller_template(name: &str) -> String {
+pub fn simple_controller_template(name: &str, id: &str) -> String {
     let cap = capitalize(name);
     format!(
         r#"use axum::{{extract::Path, http::StatusCode, Json}};
@@ -19,17 +39,17 @@ pub async fn find_all() -> (StatusCode, String) {{
 }}
 
 // Get one {name} by ID
-pub async fn find_one(Path(id): Path<u32>) -> (StatusCode, String) {{
+pub async fn find_one(Path(id): Path<{id}>) -> (StatusCode, String) {{
     (StatusCode::OK, format!("Find {cap} with id {{}}", id))
 }}
 
 // Update a {name} by ID
-pub async fn update(Path(id): Path<u32>) -> (StatusCode, String) {{
+pub async fn update(Path(id): Path<{id}>) -> (StatusCode, String) {{
     (StatusCode::OK, format!("Update {cap} with id {{}}", id))
 }}
 
 // Remove a {name} by ID
-pub async fn remove(Path(id): Path<u32>) -> (StatusCode, String) {{
+pub async fn remove(Path(id): Path<{id}>) -> (StatusCode, String) {{
     (StatusCode::OK, format!("Remove {cap} with id {{}}", id))
 }}
 "#,
@@ -41,28 +61,28 @@ pub async fn remove(Path(id): Path<u32>) -> (StatusCode, String) {{
 
 
 //-----------------------------------------service--------------------------------------------
-pub fn simple_service_template(name: &str) -> String {
+pub fn simple_service_template(name: &str, id: &str) -> String {
     let cap = capitalize(name);
     format!(
         r#"// Service functions for {cap}
 
-pub fn create() -> String {{
+pub async fn create() -> String {{
     "Create {cap}".to_string()
 }}
 
-pub fn find_all() -> String {{
+pub async fn find_all() -> String {{
     "Find all {cap}s".to_string()
 }}
 
-pub fn find_one(id: u32) -> String {{
+pub async fn find_one(id: {id}) -> String {{
     format!("Find {cap} with id {{}}", id)
 }}
 
-pub fn update(id: u32) -> String {{
+pub async fn update(id: {id}) -> String {{
     format!("Update {cap} with id {{}}", id)
 }}
 
-pub fn remove(id: u32) -> String {{
+pub async fn remove(id: {id}) -> String {{
     format!("Remove {cap} with id {{}}", id)
 }}
 "#,
@@ -71,20 +91,20 @@ pub fn remove(id: u32) -> String {{
 }
 
 //-----------------------------------------dto--------------------------------------------
-pub fn simple_dto_template(name: &str) -> String {
+pub fn simple_dto_template(name: &str, fields: &[Field]) -> String {
     let cap = capitalize(name);
+    let create_fields = struct_fields(fields, false, "// TODO: define fields");
+    let update_fields = struct_fields(fields, true, "// TODO: define fields");
     format!(
         r#"use serde::Deserialize;
 
 #[derive(Deserialize)]
 pub struct Create{cap}Dto {{
-    // TODO: define fields
-}}
+{create_fields}}}
 
 #[derive(Deserialize)]
 pub struct Update{cap}Dto {{
-    // TODO: define fields
-}}
+{update_fields}}}
 "#,
         cap = cap
     )
@@ -93,16 +113,17 @@ pub struct Update{cap}Dto {{
 
 
 //-----------------------------------------entity--------------------------------------------
-pub fn simple_entity_template(name: &str) -> String {
+pub fn simple_entity_template(name: &str, fields: &[Field], id: &str, timestamps: bool, soft_delete: bool) -> String {
     let cap = capitalize(name);
+    let mut fields = struct_fields(fields, false, "// TODO: add fields");
+    fields.push_str(&timestamp_fields_template(timestamps, soft_delete));
     format!(
         r#"use serde::{{Serialize, Deserialize}};
 
 #[derive(Clone, Serialize, Deserialize)]
 pub struct {cap} {{
-    pub id: u32,
-    // TODO: add fields
-}}
+    pub id: {id},
+{fields}}}
 "#,
         cap = cap
     )
@@ -111,17 +132,32 @@ pub struct {cap} {{
 
 //-----------------------------------------middleware--------------------------------------------
 pub fn middleware_dependencies_template() -> String {
-    format!(
-        r#"use axum::extract::Request;
+    r#"use axum::extract::Request;
 use axum::middleware::Next;
 use axum::response::Response;
-"#
-    )
+"#.to_string()
 }
 
-pub fn middleware_function_template(name: &str) -> String {
+pub fn middleware_function_template(name: &str, tracing: bool) -> String {
+    if tracing {
+        return format!(
+            r#"
+pub async fn {name}_middleware(req: Request, next: Next) -> Response {{
+    let span = tracing::info_span!("{name}_middleware", method = %req.method(), path = %req.uri().path());
+    async move {{
+        let res = next.run(req).await;
+        tracing::info!(status = %res.status(), "request handled");
+        res
+    }}
+    .instrument(span)
+    .await
+}}
+"#
+        );
+    }
     format!(
-        r#"pub async fn {name}_middleware(req: Request, next: Next) -> Response {{
+        r#"
+pub async fn {name}_middleware(req: Request, next: Next) -> Response {{
     println!("➡️ [{name}_middleware] {{}} {{}}", req.method(), req.uri().path());
     let res = next.run(req).await;
     println!("⬅️ [{name}_middleware] {{}}", res.status());
@@ -131,3 +167,441 @@ pub fn middleware_function_template(name: &str) -> String {
         name = name
     )
 }
+
+/// Marker of the shared request-id items, written once per middlewares.rs
+pub const REQUEST_ID_MARKER: &str = "pub struct RequestId";
+
+/// `RequestId` extractor and ID generator shared by request-id middlewares
+pub fn request_id_support_template() -> String {
+    r#"
+/// Header carrying the request ID between services
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// ID of the current request, set by the request-id middleware.
+/// Take it as a handler argument: `async fn handler(request_id: RequestId)`.
+#[derive(Clone, Debug)]
+#[allow(dead_code)]
+pub struct RequestId(pub String);
+
+impl<S: Send + Sync> FromRequestParts<S> for RequestId {
+    type Rejection = (StatusCode, &'static str);
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        parts
+            .extensions
+            .get::<RequestId>()
+            .cloned()
+            .ok_or((StatusCode::INTERNAL_SERVER_ERROR, "request-id middleware is not installed"))
+    }
+}
+
+static NEXT_REQUEST_ID: AtomicU64 = AtomicU64::new(0);
+
+/// Process start time plus a counter: unique per process without extra dependencies
+fn new_request_id() -> String {
+    let started = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis();
+    let seq = NEXT_REQUEST_ID.fetch_add(1, Ordering::Relaxed);
+    format!("{:x}-{:x}", started, seq)
+}
+"#
+    .to_string()
+}
+
+/// Middleware that keeps the caller's `x-request-id` (or assigns one), exposes it as `RequestId` and echoes it back
+pub fn request_id_middleware_template(name: &str, tracing: bool) -> String {
+    let run = if tracing {
+        "    // every span opened while handling the request carries its ID\n    let span = tracing::info_span!(\"request\", request_id = %id);\n    let mut res = next.run(req).instrument(span).await;\n"
+    } else {
+        "    let mut res = next.run(req).await;\n"
+    };
+    format!(
+        r#"
+pub async fn {name}_middleware(mut req: Request, next: Next) -> Response {{
+    // keep the caller's ID so logs correlate across services, otherwise start a new one
+    let id = req
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .filter(|value| !value.is_empty())
+        .map(str::to_string)
+        .unwrap_or_else(new_request_id);
+    req.extensions_mut().insert(RequestId(id.clone()));
+
+{run}    if let Ok(value) = HeaderValue::from_str(&id) {{
+        res.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }}
+    res
+}}
+"#
+    )
+}
+
+/// Middleware adding an `x-response-time-ms` header and logging the duration
+pub fn timing_middleware_template(name: &str, tracing: bool) -> String {
+    let log = if tracing {
+        "tracing::info!(elapsed_ms, \"request timed\");".to_string()
+    } else {
+        format!("println!(\"⏱️ [{name}_middleware] {{}} ms\", elapsed_ms);")
+    };
+    format!(
+        r#"
+pub async fn {name}_middleware(req: Request, next: Next) -> Response {{
+    let started = Instant::now();
+    let mut res = next.run(req).await;
+    let elapsed_ms = started.elapsed().as_millis() as u64;
+    {log}
+    res.headers_mut().insert("x-response-time-ms", HeaderValue::from(elapsed_ms));
+    res
+}}
+"#
+    )
+}
+
+/// Middleware rejecting requests without an `Authorization: Bearer <token>` header
+pub fn auth_header_middleware_template(name: &str) -> String {
+    format!(
+        r#"
+pub async fn {name}_middleware(req: Request, next: Next) -> Response {{
+    let token = req
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .filter(|token| !token.is_empty());
+    if token.is_none() {{
+        return (StatusCode::UNAUTHORIZED, "missing bearer token").into_response();
+    }}
+    // verify the token here before letting the request through
+    next.run(req).await
+}}
+"#
+    )
+}
+
+/// Middleware setting common security headers on every response
+pub fn security_headers_middleware_template(name: &str) -> String {
+    format!(
+        r#"
+pub async fn {name}_middleware(req: Request, next: Next) -> Response {{
+    let mut res = next.run(req).await;
+    let headers = res.headers_mut();
+    for (name, value) in [
+        ("x-content-type-options", "nosniff"),
+        ("x-frame-options", "DENY"),
+        ("referrer-policy", "no-referrer"),
+        ("strict-transport-security", "max-age=31536000; includeSubDomains"),
+    ] {{
+        headers.insert(name, HeaderValue::from_static(value));
+    }}
+    res
+}}
+"#
+    )
+}
+
+/// Middleware allowing a fixed number of requests per second across all clients
+pub fn rate_limit_middleware_template(name: &str) -> String {
+    let upp = name.to_uppercase();
+    format!(
+        r#"
+/// Requests `{name}_middleware` lets through per second, across all clients
+const {upp}_MAX_PER_SECOND: u32 = 100;
+
+/// Start of the current one-second window and the requests seen in it
+static {upp}_WINDOW: Lazy<Mutex<(Instant, u32)>> = Lazy::new(|| Mutex::new((Instant::now(), 0)));
+
+pub async fn {name}_middleware(req: Request, next: Next) -> Response {{
+    let allowed = {{
+        let mut window = {upp}_WINDOW.lock().unwrap();
+        if window.0.elapsed() >= Duration::from_secs(1) {{
+            *window = (Instant::now(), 0);
+        }}
+        window.1 += 1;
+        window.1 <= {upp}_MAX_PER_SECOND
+    }};
+    if !allowed {{
+        return (StatusCode::TOO_MANY_REQUESTS, "rate limit exceeded").into_response();
+    }}
+    next.run(req).await
+}}
+"#
+    )
+}
+
+/// Marks middlewares.rs as already holding the API key helpers
+pub const API_KEY_MARKER: &str = "pub fn verify_api_key";
+
+/// `ApiClient` extension and key verification shared by api-key middlewares
+pub fn api_key_support_template() -> String {
+    r#"
+/// Header carrying the API key of service-to-service calls
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+/// Name of the configured key a request came in with; handlers can take `Extension<ApiClient>`
+#[derive(Clone, Debug)]
+#[allow(dead_code)]
+pub struct ApiClient(pub String);
+
+/// The configured key whose SHA-256 digest matches `presented`.
+/// Every key is compared in constant time, so response times do not reveal how close a guess was.
+pub fn verify_api_key(presented: &str) -> Option<&'static ApiKey> {
+    let digest: String = Sha256::digest(presented.as_bytes()).iter().map(|b| format!("{:02x}", b)).collect();
+    let mut found = None;
+    for key in &CONFIG.api_keys {
+        if constant_time_eq(digest.as_bytes(), key.sha256.to_ascii_lowercase().as_bytes()) {
+            found = Some(key);
+        }
+    }
+    found
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+"#
+    .to_string()
+}
+
+/// Middleware letting through requests whose API key carries the `{name}` scope
+pub fn api_key_middleware_template(name: &str) -> String {
+    format!(
+        r#"
+/// Accept requests with an `x-api-key` configured for the `{name}` scope (or `*`); others get 401/403.
+pub async fn {name}_middleware(mut req: Request, next: Next) -> Response {{
+    let Some(presented) = req.headers().get(API_KEY_HEADER).and_then(|value| value.to_str().ok()) else {{
+        return (StatusCode::UNAUTHORIZED, "missing x-api-key").into_response();
+    }};
+    let Some(key) = verify_api_key(presented) else {{
+        return (StatusCode::UNAUTHORIZED, "invalid api key").into_response();
+    }};
+    if !key.scopes.iter().any(|scope| scope == "{name}" || scope == "*") {{
+        return (StatusCode::FORBIDDEN, "api key lacks the `{name}` scope").into_response();
+    }}
+    req.extensions_mut().insert(ApiClient(key.name.clone()));
+    next.run(req).await
+}}
+"#
+    )
+}
+
+/// `ApiKey` settings appended to src/config.rs for api-key middlewares
+pub fn api_key_config_template() -> String {
+    r#"
+/// A key accepted by the api-key middlewares. Only its SHA-256 is stored:
+/// `printf %s "$KEY" | sha256sum` gives the hex digest.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ApiKey {
+    /// Who uses the key, exposed to handlers as `ApiClient`
+    pub name: String,
+    /// Hex SHA-256 digest of the key
+    pub sha256: String,
+    /// Middleware names the key passes, or `*` for all
+    pub scopes: Vec<String>,
+}
+"#
+    .to_string()
+}
+
+/// Commented example appended to config/default.toml for api-key middlewares
+pub fn api_keys_toml_template() -> String {
+    r#"
+# API keys for service-to-service calls; store the SHA-256 of each key, never the key itself
+# [[api_keys]]
+# name = "billing"
+# sha256 = "<hex SHA-256 of the key>"
+# scopes = ["internal"]
+"#
+    .to_string()
+}
+
+/// `tower-http` CORS layer
+pub fn cors_layer_template(name: &str) -> String {
+    format!(
+        r#"
+/// CORS policy; narrow `allow_origin` to your front-end's origin for production
+pub fn {name}_layer() -> CorsLayer {{
+    CorsLayer::new()
+        .allow_origin(Any)
+        .allow_methods(Any)
+        .allow_headers(Any)
+}}
+"#
+    )
+}
+
+/// `tower-http` gzip compression layer
+pub fn compression_layer_template(name: &str) -> String {
+    format!(
+        r#"
+/// Gzip responses for clients that accept it
+pub fn {name}_layer() -> CompressionLayer {{
+    CompressionLayer::new()
+}}
+"#
+    )
+}
+
+/// `tower-http` timeout layer
+pub fn timeout_layer_template(name: &str) -> String {
+    format!(
+        r#"
+/// Answer 408 when a request takes longer than 30 seconds
+pub fn {name}_layer() -> TimeoutLayer {{
+    TimeoutLayer::with_status_code(StatusCode::REQUEST_TIMEOUT, Duration::from_secs(30))
+}}
+"#
+    )
+}
+
+/// `tower-http` request body limit layer
+pub fn body_limit_layer_template(name: &str) -> String {
+    format!(
+        r#"
+/// Answer 413 to request bodies over 2 MiB
+pub fn {name}_layer() -> RequestBodyLimitLayer {{
+    RequestBodyLimitLayer::new(2 * 1024 * 1024)
+}}
+"#
+    )
+}
+
+
+//-----------------------------------------layer--------------------------------------------
+/// src/layers/{name}.rs: a tower `Layer` + `Service` pair configured by a `{Name}Config`
+pub fn layer_template(name: &str) -> String {
+    let cap = capitalize(name);
+    format!(
+        r#"use axum::extract::Request;
+use axum::response::Response;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{{Context, Poll}};
+use tower::{{Layer, Service}};
+
+/// Settings for [`{cap}Layer`]
+#[derive(Clone, Debug)]
+pub struct {cap}Config {{
+    /// Pass requests straight through when false
+    pub enabled: bool,
+}}
+
+impl Default for {cap}Config {{
+    fn default() -> Self {{
+        {cap}Config {{ enabled: true }}
+    }}
+}}
+
+/// Wraps services in [`{cap}Service`]; attach with `.layer({cap}Layer::new(config))`
+#[derive(Clone, Debug, Default)]
+pub struct {cap}Layer {{
+    config: {cap}Config,
+}}
+
+impl {cap}Layer {{
+    pub fn new(config: {cap}Config) -> Self {{
+        {cap}Layer {{ config }}
+    }}
+}}
+
+impl<S> Layer<S> for {cap}Layer {{
+    type Service = {cap}Service<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {{
+        {cap}Service {{ inner, config: self.config.clone() }}
+    }}
+}}
+
+#[derive(Clone, Debug)]
+pub struct {cap}Service<S> {{
+    inner: S,
+    config: {cap}Config,
+}}
+
+impl<S> Service<Request> for {cap}Service<S>
+where
+    S: Service<Request, Response = Response> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {{
+        self.inner.poll_ready(cx)
+    }}
+
+    fn call(&mut self, req: Request) -> Self::Future {{
+        // `self.inner` is the clone poll_ready drove to readiness: use it, leave a fresh clone behind
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let config = self.config.clone();
+
+        Box::pin(async move {{
+            if !config.enabled {{
+                return inner.call(req).await;
+            }}
+            // inspect or change the request here
+            let response = inner.call(req).await?;
+            // inspect or change the response here
+            Ok(response)
+        }})
+    }}
+}}
+"#
+    )
+}
+
+
+//-----------------------------------------guard--------------------------------------------
+/// src/guards/{name}.rs: an extractor that lets a handler run only for allowed requests
+pub fn guard_template(name: &str, error_handling: bool) -> String {
+    let cap = capitalize(name);
+    let (uses, rejection, unauthorized, forbidden) = if error_handling {
+        (
+            "use crate::error::AppError;\n",
+            "AppError",
+            "AppError::Unauthorized(\"missing bearer token\".to_string())",
+            "AppError::Forbidden(\"not allowed\".to_string())",
+        )
+    } else {
+        (
+            "use axum::http::StatusCode;\n",
+            "(StatusCode, &'static str)",
+            "(StatusCode::UNAUTHORIZED, \"missing bearer token\")",
+            "(StatusCode::FORBIDDEN, \"not allowed\")",
+        )
+    };
+    format!(
+        r#"use axum::extract::FromRequestParts;
+use axum::http::header;
+use axum::http::request::Parts;
+{uses}
+/// Guards a handler: add `_guard: {cap}Guard` to its parameters.
+/// Rejects with 401 when the caller is not authenticated and 403 when it may not go on.
+pub struct {cap}Guard;
+
+impl<S: Send + Sync> FromRequestParts<S> for {cap}Guard {{
+    type Rejection = {rejection};
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {{
+        let token = parts
+            .headers
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .filter(|token| !token.is_empty())
+            .ok_or({unauthorized})?;
+        if !is_allowed(token) {{
+            return Err({forbidden});
+        }}
+        Ok({cap}Guard)
+    }}
+}}
+
+/// Decide whether the caller may go on, e.g. look the token up or check a role
+fn is_allowed(token: &str) -> bool {{
+    !token.is_empty()
+}}
+"#
+    )
+}
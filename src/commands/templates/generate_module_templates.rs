@@ -1,44 +1,185 @@
+use anyhow::Result;
 use crate::utils::common::{capitalize};
+use crate::utils::field::Field;
+use crate::utils::file::module_dir;
+use crate::utils::project::{IdStrategy, ProjectConfig};
+use std::path::PathBuf;
+
+/// Query parameters `find_all` reads itself, so no field can filter under these names
+const LIST_PARAMS: [&str; 3] = ["page", "per_page", "sort"];
+
+/// Entity fields of `--timestamps` and `--soft-delete`, which the service sets rather than the DTOs
+pub const TIMESTAMP_FIELDS: [&str; 3] = ["created_at", "updated_at", "deleted_at"];
+
+use super::rbac_templates::CRUD_PERMISSIONS;
+
+/// Everything the module templates need to know about the module being generated
+pub struct ModuleSpec {
+    pub name: String,
+    pub fields: Vec<Field>,
+    pub project: ProjectConfig,
+    /// Guard each CRUD handler with a `RequirePermission` for `{name}:create`, `{name}:read`...
+    pub permissions: bool,
+    /// API version namespace, e.g. `v2`: the module lives in src/v2/{name} and is served under `/v2/{name}`
+    pub version: Option<String>,
+    /// Type of the entity id and how the service makes new ones
+    pub id: IdStrategy,
+    /// `created_at` and `updated_at`, kept by the service
+    pub timestamps: bool,
+    /// `remove` sets `deleted_at` instead of deleting, and the finders skip rows that have it
+    pub soft_delete: bool,
+}
+
+impl ModuleSpec {
+    /// Build a spec for `name` using the settings of the project in the current directory.
+    /// Without explicit fields the module gets the `field1`/`field2` placeholders.
+    pub fn new(name: &str, fields: Vec<Field>) -> Result<Self> {
+        let fields = if fields.is_empty() { Field::defaults() } else { fields };
+        let project = ProjectConfig::load()?;
+        Ok(ModuleSpec {
+            name: name.to_string(),
+            fields,
+            id: project.id_strategy(),
+            project,
+            permissions: false,
+            version: None,
+            timestamps: false,
+            soft_delete: false,
+        })
+    }
+
+    /// `#[tracing::instrument]` lines for service functions (empty without tracing); the second skips the DTO argument
+    pub fn spans(&self) -> (&'static str, &'static str) {
+        if self.project.tracing {
+            ("#[tracing::instrument]\n", "#[tracing::instrument(skip(dto))]\n")
+        } else {
+            ("", "")
+        }
+    }
+
+    /// Rust type of the entity id
+    pub fn id_type(&self) -> &'static str {
+        self.id.rust_type()
+    }
+
+    /// Path of the module from the crate root, e.g. `hero` or `v2::hero`
+    pub fn module_path(&self) -> String {
+        match &self.version {
+            Some(version) => format!("{}::{}", version, self.name),
+            None => self.name.clone(),
+        }
+    }
+
+    /// Directory of the module, e.g. src/hero or src/v2/hero
+    pub fn dir(&self) -> PathBuf {
+        match &self.version {
+            Some(version) => module_dir(version).join(&self.name),
+            None => module_dir(&self.name),
+        }
+    }
+
+    /// Timestamps of the entity the service maintains, which `find_all` can also sort by
+    pub fn timestamp_fields(&self) -> &'static [&'static str] {
+        if self.timestamps { &TIMESTAMP_FIELDS[..2] } else { &[] }
+    }
+
+    /// Fields `find_all` can filter on with `?field=value`
+    pub fn filters(&self) -> impl Iterator<Item = &Field> {
+        self.fields.iter().filter(|f| !LIST_PARAMS.contains(&f.name.as_str()))
+    }
+}
 
 /// Template strings for generated files
-pub fn controller_template(name: &str) -> String {
+pub fn controller_template(spec: &ModuleSpec) -> String {
+    let name = spec.name.as_str();
+    let path = spec.module_path();
     let cap = capitalize(name);
+    let id = spec.id_type();
+    let doc = |attr: String| if spec.project.openapi { attr } else { String::new() };
+
+    let create_doc = doc(format!(
+        "#[utoipa::path(post, path = \"/\", tag = \"{name}\", request_body = Create{cap}Dto, responses((status = 201, body = String)))]\n"
+    ));
+    let find_all_doc = doc(format!(
+        "#[utoipa::path(get, path = \"/\", tag = \"{name}\", params({cap}ListQuery), responses((status = 200, body = Paginated<{cap}>), (status = 400, body = String)))]\n"
+    ));
+    // the in-memory service cannot fail, the SQL one reports database errors
+    let listed = if spec.project.database.is_sql() {
+        ".await.map_err(|err| (StatusCode::INTERNAL_SERVER_ERROR, err))?"
+    } else {
+        ".await"
+    };
+    let find_one_doc = doc(format!(
+        "#[utoipa::path(get, path = \"/{{id}}\", tag = \"{name}\", params((\"id\" = {id}, Path)), responses((status = 200, body = String)))]\n"
+    ));
+    let update_doc = doc(format!(
+        "#[utoipa::path(patch, path = \"/{{id}}\", tag = \"{name}\", params((\"id\" = {id}, Path)), request_body = Update{cap}Dto, responses((status = 200, body = String)))]\n"
+    ));
+    let remove_doc = doc(format!(
+        "#[utoipa::path(delete, path = \"/{{id}}\", tag = \"{name}\", params((\"id\" = {id}, Path)), responses((status = 200, body = String)))]\n"
+    ));
+
+    // `_permission: RequirePermission<..>` leading each handler's parameters, and its imports
+    let permission = |handler: &str, more_params: bool| match CRUD_PERMISSIONS.iter().find(|(_, h)| h.contains(&handler)) {
+        Some((action, _)) if spec.permissions => {
+            let separator = if more_params { ", " } else { "" };
+            format!("_permission: RequirePermission<{}{}>{}", cap, capitalize(action), separator)
+        }
+        _ => String::new(),
+    };
+    let permission_uses = if spec.permissions {
+        let types = CRUD_PERMISSIONS.iter().map(|(action, _)| format!("{}{}", cap, capitalize(action))).collect::<Vec<_>>();
+        format!("use crate::rbac::RequirePermission;\nuse crate::{path}::{name}_permissions::{{{}}};\n", types.join(", "))
+    } else {
+        String::new()
+    };
+    let (create_perm, find_all_perm, find_one_perm, update_perm, remove_perm) = (
+        permission("create", true),
+        permission("find_all", true),
+        permission("find_one", true),
+        permission("update", true),
+        permission("remove", true),
+    );
+
     format!(
         r#"use axum::{{
-    extract::Path,
+    extract::{{Path, Query}},
     http::StatusCode,
     Json,
 }};
-use crate::{name}::dto::{name}_dto::{{Create{cap}Dto, Update{cap}Dto}};
-use crate::{name}::{name}_service;
-
+use crate::pagination::{{Paginated, Sort}};
+use crate::{path}::dto::{name}_dto::{{Create{cap}Dto, {cap}ListQuery, Update{cap}Dto}};
+use crate::{path}::entities::{name}_entity::{cap};
+use crate::{path}::{name}_service;
+{permission_uses}
 // Create a {name}
-pub async fn create(Json(dto): Json<Create{cap}Dto>) -> (StatusCode, String) {{
-    let message = {name}_service::create(dto);
+{create_doc}pub async fn create({create_perm}Json(dto): Json<Create{cap}Dto>) -> (StatusCode, String) {{
+    let message = {name}_service::create(dto).await;
     (StatusCode::CREATED, message)
 }}
 
-// Get all {name}s
-pub async fn find_all() -> (StatusCode, String) {{
-    let message = {name}_service::find_all();
-    (StatusCode::OK, message)
+// Get a page of {name}s, filtered and sorted by the query
+{find_all_doc}pub async fn find_all({find_all_perm}Query(query): Query<{cap}ListQuery>) -> Result<Json<Paginated<{cap}>>, (StatusCode, String)> {{
+    let sort = Sort::parse(query.sort.as_deref(), {cap}ListQuery::SORTABLE).map_err(|err| (StatusCode::BAD_REQUEST, err))?;
+    let page = {name}_service::find_all(&query, sort){listed};
+    Ok(Json(page))
 }}
 
 // Get one {name} by ID
-pub async fn find_one(Path(id): Path<u32>) -> (StatusCode, String) {{
-    let message = {name}_service::find_one(id);
+{find_one_doc}pub async fn find_one({find_one_perm}Path(id): Path<{id}>) -> (StatusCode, String) {{
+    let message = {name}_service::find_one(id).await;
     (StatusCode::OK, message)
 }}
 
 // Update a {name} by ID
-pub async fn update(Path(id): Path<u32>, Json(dto): Json<Update{cap}Dto>) -> (StatusCode, String) {{
-    let message = {name}_service::update(id, dto);
+{update_doc}pub async fn update({update_perm}Path(id): Path<{id}>, Json(dto): Json<Update{cap}Dto>) -> (StatusCode, String) {{
+    let message = {name}_service::update(id, dto).await;
     (StatusCode::OK, message)
 }}
 
 // Remove a {name} by ID
-pub async fn remove(Path(id): Path<u32>) -> (StatusCode, String) {{
-    let message = {name}_service::remove(id);
+{remove_doc}pub async fn remove({remove_perm}Path(id): Path<{id}>) -> (StatusCode, String) {{
+    let message = {name}_service::remove(id).await;
     (StatusCode::OK, message)
 }}
 "#,
@@ -48,62 +189,156 @@ pub async fn remove(Path(id): Path<u32>) -> (StatusCode, String) {{
 }
 
 
-pub fn service_template(name: &str) -> String {
+pub fn service_template(spec: &ModuleSpec) -> String {
+    if spec.project.database.is_sql() {
+        return sql_service_template(spec);
+    }
+
+    let name = spec.name.as_str();
+    let path = spec.module_path();
     let cap = capitalize(name);
     let upp = name.to_uppercase();
-    format!(
-        r#"use crate::{name}::dto::{name}_dto::{{Create{cap}Dto, Update{cap}Dto}};
-use crate::{name}::entities::{name}_entity::{cap};
-use std::sync::Mutex;
-use once_cell::sync::Lazy;
+    let (span, span_dto) = spec.spans();
+    let assignments = spec
+        .fields
+        .iter()
+        .map(|f| format!("{0}: dto.{0}", f.name))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let updates: String = spec
+        .fields
+        .iter()
+        .map(|f| {
+            format!(
+                "        if let Some({field}) = dto.{field} {{\n            {name}.{field} = {field};\n        }}\n",
+                field = f.name,
+                name = name
+            )
+        })
+        .collect();
+    let filters: String = spec
+        .filters()
+        .map(|f| format!("        .filter(|item| query.{0}.as_ref().is_none_or(|value| item.{0} == *value))\n", f.name))
+        .collect();
+    let orderings: String = spec
+        .fields
+        .iter()
+        .map(|f| f.name.as_str())
+        .chain(spec.timestamp_fields().iter().copied())
+        .map(|field| format!("                \"{0}\" => a.{0}.partial_cmp(&b.{0}),\n", field))
+        .collect();
+    let (now, stamps, touch) = if spec.timestamps {
+        (
+            "    let now = chrono::Utc::now();\n",
+            ", created_at: now, updated_at: now",
+            format!("        {}.updated_at = chrono::Utc::now();\n", name),
+        )
+    } else {
+        ("", "", String::new())
+    };
+    // `remove` sets `deleted_at` instead of deleting, and find_all, find_one and update skip rows that have it
+    let (live, not_deleted, live_filter) = if spec.soft_delete {
+        (", deleted_at: None", " && b.deleted_at.is_none()", "        .filter(|item| item.deleted_at.is_none())\n")
+    } else {
+        ("", "", "")
+    };
+    let removal = if spec.soft_delete {
+        format!(
+            r#"    if let Some({name}) = {name}s.iter_mut().find(|b| b.id == id && b.deleted_at.is_none()) {{
+        {name}.deleted_at = Some(chrono::Utc::now());
+        format!("{cap} with id {{}} removed", id)
+    }} else {{
+        format!("{cap} with id {{}} not found", id)
+    }}"#
+        )
+    } else {
+        format!(
+            r#"    let len_before = {name}s.len();
+    {name}s.retain(|b| b.id != id);
+    if {name}s.len() < len_before {{
+        format!("{cap} with id {{}} removed", id)
+    }} else {{
+        format!("{cap} with id {{}} not found", id)
+    }}"#
+        )
+    };
+    let id = spec.id_type();
+    // counted ids come from a counter, so ids of removed {name}s are never handed out again
+    let (counter, new_id) = match spec.id {
+        IdStrategy::U32 | IdStrategy::I64 => {
+            let atomic = if spec.id == IdStrategy::U32 { "AtomicU32" } else { "AtomicI64" };
+            (
+                format!("use std::sync::atomic::{{self, {atomic}}};\n"),
+                format!("static NEXT_ID: {atomic} = {atomic}::new(1);\n"),
+            )
+        }
+        _ => (String::new(), String::new()),
+    };
+    let generate_id = match spec.id {
+        IdStrategy::U32 | IdStrategy::I64 => "NEXT_ID.fetch_add(1, atomic::Ordering::Relaxed)",
+        IdStrategy::Uuid => "uuid::Uuid::now_v7()",
+        IdStrategy::Ulid => "ulid::Ulid::new()",
+    };
 
-static {upp}S: Lazy<Mutex<Vec<{cap}>>> = Lazy::new(|| Mutex::new(Vec::new()));
+    format!(
+        r#"use crate::pagination::{{Page, Paginated, Sort}};
+use crate::{path}::dto::{name}_dto::{{Create{cap}Dto, {cap}ListQuery, Update{cap}Dto}};
+use crate::{path}::entities::{name}_entity::{cap};
+use std::cmp::Ordering;
+{counter}use once_cell::sync::Lazy;
+use tokio::sync::RwLock;
 
-pub fn create(dto: Create{cap}Dto) -> String {{
-    let mut {name}s = {upp}S.lock().unwrap();
-    let id = ({name}s.len() + 1) as u32;
-    let {name} = {cap} {{ id, field1: dto.field1, field2: dto.field2 }};
+static {upp}S: Lazy<RwLock<Vec<{cap}>>> = Lazy::new(|| RwLock::new(Vec::new()));
+{new_id}
+{span_dto}pub async fn create(dto: Create{cap}Dto) -> String {{
+    let mut {name}s = {upp}S.write().await;
+    let id = {generate_id};
+{now}    let {name} = {cap} {{ id, {assignments}{stamps}{live} }};
     {name}s.push({name});
     format!("{cap} created with id {{}}", id)
 }}
 
-pub fn find_all() -> String {{
-    let {name}s = {upp}S.lock().unwrap();
-    format!("There are {{}} {name}s in total", {name}s.len())
+{span}pub async fn find_all(query: &{cap}ListQuery, sort: Option<Sort>) -> Paginated<{cap}> {{
+    let {name}s = {upp}S.read().await;
+    let mut matching: Vec<{cap}> = {name}s
+        .iter()
+{live_filter}{filters}        .cloned()
+        .collect();
+    if let Some(sort) = sort {{
+        matching.sort_by(|a, b| {{
+            let ordering = match sort.field {{
+{orderings}                _ => a.id.partial_cmp(&b.id),
+            }}
+            .unwrap_or(Ordering::Equal);
+            if sort.descending {{ ordering.reverse() }} else {{ ordering }}
+        }});
+    }}
+    let page = Page::new(query.page, query.per_page);
+    let total = matching.len() as u64;
+    let items = matching.into_iter().skip(page.offset() as usize).take(page.per_page as usize).collect();
+    Paginated::new(items, page, total)
 }}
 
-pub fn find_one(id: u32) -> String {{
-    let {name}s = {upp}S.lock().unwrap();
-    if {name}s.iter().any(|b| b.id == id) {{
+{span}pub async fn find_one(id: {id}) -> String {{
+    let {name}s = {upp}S.read().await;
+    if {name}s.iter().any(|b| b.id == id{not_deleted}) {{
         format!("Found {name} with id {{}}", id)
     }} else {{
         format!("{cap} with id {{}} not found", id)
     }}
 }}
 
-pub fn update(id: u32, dto: Update{cap}Dto) -> String {{
-    let mut {name}s = {upp}S.lock().unwrap();
-    if let Some({name}) = {name}s.iter_mut().find(|b| b.id == id) {{
-        if let Some(field1) = dto.field1 {{
-            {name}.field1 = field1;
-        }}
-        if let Some(field2) = dto.field2 {{
-            {name}.field2 = field2;
-        }}
-        return format!("{cap} with id {{}} updated", id);
+{span_dto}pub async fn update(id: {id}, dto: Update{cap}Dto) -> String {{
+    let mut {name}s = {upp}S.write().await;
+    if let Some({name}) = {name}s.iter_mut().find(|b| b.id == id{not_deleted}) {{
+{updates}{touch}        return format!("{cap} with id {{}} updated", id);
     }}
     format!("{cap} with id {{}} not found", id)
 }}
 
-pub fn remove(id: u32) -> String {{
-    let mut {name}s = {upp}S.lock().unwrap();
-    let len_before = {name}s.len();
-    {name}s.retain(|b| b.id != id);
-    if {name}s.len() < len_before {{
-        format!("{cap} with id {{}} removed", id)
-    }} else {{
-        format!("{cap} with id {{}} not found", id)
-    }}
+{span}pub async fn remove(id: {id}) -> String {{
+    let mut {name}s = {upp}S.write().await;
+{removal}
 }}
 "#,
         name = name,
@@ -113,22 +348,192 @@ pub fn remove(id: u32) -> String {{
 }
 
 
-
-pub fn dto_template(name: &str) -> String {
+/// Service backed by the sqlx pool from `crate::db`
+fn sql_service_template(spec: &ModuleSpec) -> String {
+    let name = spec.name.as_str();
+    let path = spec.module_path();
     let cap = capitalize(name);
+    let stamps = spec.timestamp_fields();
+    let columns = spec.fields.iter().map(|f| f.name.as_str()).chain(stamps.iter().copied()).collect::<Vec<_>>().join(", ");
+    let inserted = spec.fields.len() + stamps.len();
+    let params = (1..=inserted).map(|i| format!("${}", i)).collect::<Vec<_>>().join(", ");
+    let mut sets = spec
+        .fields
+        .iter()
+        .enumerate()
+        .map(|(i, f)| format!("{0} = COALESCE(${1}, {0})", f.name, i + 1))
+        .collect::<Vec<_>>();
+    let id = spec.id_type();
+    let binds: String = spec.fields.iter().map(|f| format!("        .bind(dto.{})\n", f.name)).collect();
+    let mut update_binds = binds.clone();
+    if spec.timestamps {
+        sets.push(format!("updated_at = ${}", sets.len() + 1));
+        update_binds.push_str("        .bind(chrono::Utc::now())\n");
+    }
+    let id_param = sets.len() + 1;
+    let sets = sets.join(", ");
+    let create_binds = format!("{}{}", binds, "        .bind(now)\n".repeat(stamps.len()));
+    let now = if spec.timestamps { "let now = chrono::Utc::now();\n    " } else { "" };
+    // the database counts integer ids; random ones are made here and inserted with the row
+    let create = if spec.id.is_random() {
+        let params = (1..=inserted + 1).map(|i| format!("${}", i)).collect::<Vec<_>>().join(", ");
+        format!(
+            r#"{now}let id = uuid::Uuid::now_v7();
+    let result = sqlx::query("INSERT INTO {name}s (id, {columns}) VALUES ({params})")
+        .bind(id)
+{create_binds}        .execute(db::pool())
+        .await
+        .map(|_| id);"#
+        )
+    } else {
+        format!(
+            r#"{now}let result = sqlx::query_scalar::<_, {id}>("INSERT INTO {name}s ({columns}) VALUES ({params}) RETURNING id")
+{create_binds}        .fetch_one(db::pool())
+        .await;"#
+        )
+    };
+    // `remove` sets `deleted_at` instead of deleting, and every other query filters out rows that have it
+    let not_deleted = if spec.soft_delete { " AND deleted_at IS NULL" } else { "" };
+    let (removal, removal_binds) = if spec.soft_delete {
+        (
+            format!("UPDATE {name}s SET deleted_at = $1 WHERE id = $2 AND deleted_at IS NULL"),
+            "        .bind(chrono::Utc::now())\n        .bind(id)\n",
+        )
+    } else {
+        (format!("DELETE FROM {name}s WHERE id = $1"), "        .bind(id)\n")
+    };
+    let mut filters: Vec<String> = spec
+        .filters()
+        .enumerate()
+        .map(|(i, f)| format!("(${1} IS NULL OR {0} = ${1})", f.name, i + 1))
+        .collect();
+    let (limit_param, offset_param) = (filters.len() + 1, filters.len() + 2);
+    if spec.soft_delete {
+        filters.insert(0, "deleted_at IS NULL".to_string());
+    }
+    let filters = if filters.is_empty() { "1 = 1".to_string() } else { filters.join(" AND ") };
+    let filter_binds: String = spec.filters().map(|f| format!("        .bind(&query.{})\n", f.name)).collect();
+    let (span, span_dto) = spec.spans();
+
+    format!(
+        r#"use crate::db;
+use crate::pagination::{{Page, Paginated, Sort}};
+use crate::{path}::dto::{name}_dto::{{Create{cap}Dto, {cap}ListQuery, Update{cap}Dto}};
+use crate::{path}::entities::{name}_entity::{cap};
+use sqlx::AssertSqlSafe;
+
+/// `WHERE` clause of `find_all`; a filter left out of the query matches every row
+const FILTERS: &str = "{filters}";
+
+{span_dto}pub async fn create(dto: Create{cap}Dto) -> String {{
+    {create}
+    match result {{
+        Ok(id) => format!("{cap} created with id {{}}", id),
+        Err(err) => format!("Failed to create {name}: {{}}", err),
+    }}
+}}
+
+{span}pub async fn find_all(query: &{cap}ListQuery, sort: Option<Sort>) -> Result<Paginated<{cap}>, String> {{
+    let page = Page::new(query.page, query.per_page);
+    let total = sqlx::query_scalar::<_, i64>(AssertSqlSafe(format!("SELECT COUNT(*) FROM {name}s WHERE {{}}", FILTERS)))
+{filter_binds}        .fetch_one(db::pool())
+        .await
+        .map_err(|err| format!("Failed to count {name}s: {{}}", err))?;
+    // the sort field comes from `{cap}ListQuery::SORTABLE`, never from the client as is
+    let sql = format!(
+        "SELECT * FROM {name}s WHERE {{}} ORDER BY {{}} LIMIT ${limit_param} OFFSET ${offset_param}",
+        FILTERS,
+        Sort::order_by(sort)
+    );
+    let items = sqlx::query_as::<_, {cap}>(AssertSqlSafe(sql))
+{filter_binds}        .bind(i64::from(page.per_page))
+        .bind(page.offset() as i64)
+        .fetch_all(db::pool())
+        .await
+        .map_err(|err| format!("Failed to list {name}s: {{}}", err))?;
+    Ok(Paginated::new(items, page, total as u64))
+}}
+
+{span}pub async fn find_one(id: {id}) -> String {{
+    let result = sqlx::query_as::<_, {cap}>("SELECT * FROM {name}s WHERE id = $1{not_deleted}")
+        .bind(id)
+        .fetch_optional(db::pool())
+        .await;
+    match result {{
+        Ok(Some(_)) => format!("Found {name} with id {{}}", id),
+        Ok(None) => format!("{cap} with id {{}} not found", id),
+        Err(err) => format!("Failed to find {name}: {{}}", err),
+    }}
+}}
+
+{span_dto}pub async fn update(id: {id}, dto: Update{cap}Dto) -> String {{
+    let result = sqlx::query("UPDATE {name}s SET {sets} WHERE id = ${id_param}{not_deleted}")
+{update_binds}        .bind(id)
+        .execute(db::pool())
+        .await;
+    match result {{
+        Ok(done) if done.rows_affected() > 0 => format!("{cap} with id {{}} updated", id),
+        Ok(_) => format!("{cap} with id {{}} not found", id),
+        Err(err) => format!("Failed to update {name}: {{}}", err),
+    }}
+}}
+
+{span}pub async fn remove(id: {id}) -> String {{
+    let result = sqlx::query("{removal}")
+{removal_binds}        .execute(db::pool())
+        .await;
+    match result {{
+        Ok(done) if done.rows_affected() > 0 => format!("{cap} with id {{}} removed", id),
+        Ok(_) => format!("{cap} with id {{}} not found", id),
+        Err(err) => format!("Failed to remove {name}: {{}}", err),
+    }}
+}}
+"#
+    )
+}
+
+
+pub fn dto_template(spec: &ModuleSpec) -> String {
+    let cap = capitalize(&spec.name);
+    let schema = if spec.project.openapi { ", utoipa::ToSchema" } else { "" };
+    let create_fields: String = spec.fields.iter().map(|f| format!("    pub {}: {},\n", f.name, f.ty)).collect();
+    let update_fields: String = spec.fields.iter().map(|f| format!("    pub {}: Option<{}>,\n", f.name, f.ty)).collect();
+    let params = if spec.project.openapi { ", utoipa::IntoParams)]\n#[into_params(parameter_in = Query" } else { "" };
+    let filter_fields: String = spec
+        .filters()
+        .map(|f| format!("    /// Only {}s whose `{}` equals this\n    pub {}: Option<{}>,\n", spec.name, f.name, f.name, f.ty))
+        .collect();
+    let sortable = std::iter::once("id")
+        .chain(spec.fields.iter().map(|f| f.name.as_str()))
+        .chain(spec.timestamp_fields().iter().copied())
+        .map(|f| format!("\"{}\"", f))
+        .collect::<Vec<_>>()
+        .join(", ");
     format!(
         r#"use serde::Deserialize;
 
-#[derive(Deserialize)]
+#[derive(Deserialize{schema})]
 pub struct Create{cap}Dto {{
-    pub field1: String,
-    pub field2: String,
-}}
+{create_fields}}}
 
-#[derive(Deserialize)]
+#[derive(Deserialize{schema})]
 pub struct Update{cap}Dto {{
-    pub field1: Option<String>,
-    pub field2: Option<String>,
+{update_fields}}}
+
+/// Query of `find_all`: paging, sorting and one filter per field
+#[derive(Debug, Deserialize{params})]
+pub struct {cap}ListQuery {{
+    /// Page number, starting at 1
+    pub page: Option<u32>,
+    /// Items per page, 20 by default and at most 100
+    pub per_page: Option<u32>,
+    /// Field to sort by, prefixed with `-` for descending order, e.g. `-id`
+    pub sort: Option<String>,
+{filter_fields}}}
+
+impl {cap}ListQuery {{
+    /// Fields `sort` accepts
+    pub const SORTABLE: &'static [&'static str] = &[{sortable}];
 }}
 "#,
         cap = cap
@@ -136,18 +541,310 @@ pub struct Update{cap}Dto {{
 }
 
 
-pub fn entity_template(name: &str) -> String {
-    let cap = capitalize(name);
+pub fn entity_template(spec: &ModuleSpec) -> String {
+    let cap = capitalize(&spec.name);
+    let id = spec.id_type();
+    let row = if spec.project.database.is_sql() { ", sqlx::FromRow" } else { "" };
+    let schema = if spec.project.openapi { ", utoipa::ToSchema" } else { "" };
+    let fields: String = spec.fields.iter().map(|f| format!("    pub {}: {},\n", f.name, f.ty)).collect();
+    let stamps = timestamp_fields_template(spec.timestamps, spec.soft_delete);
     format!(
         r#"use serde::{{Serialize, Deserialize}};
 
-#[derive(Clone, Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize{row}{schema})]
 pub struct {cap} {{
-    pub id: u32,
-    pub field1: String,
-    pub field2: String,
-}}
+    pub id: {id},
+{fields}{stamps}}}
 "#,
         cap = cap
     )
 }
+
+
+/// Entity fields of `--timestamps` and `--soft-delete`, empty without either
+pub fn timestamp_fields_template(timestamps: bool, soft_delete: bool) -> String {
+    let mut fields = String::new();
+    if timestamps {
+        fields.push_str("    pub created_at: chrono::DateTime<chrono::Utc>,\n    pub updated_at: chrono::DateTime<chrono::Utc>,\n");
+    }
+    if soft_delete {
+        fields.push_str("    /// Set by `remove`: the row is kept, but no longer found\n    pub deleted_at: Option<chrono::DateTime<chrono::Utc>>,\n");
+    }
+    fields
+}
+
+/// SQL migration creating the module's table
+pub fn migration_template(spec: &ModuleSpec) -> Result<String> {
+    let id_column = match (spec.id.is_random(), spec.project.database) {
+        (true, database) => format!("id {} PRIMARY KEY", spec.id.sql_type(database)),
+        (false, crate::utils::project::Database::Postgres) => "id BIGSERIAL PRIMARY KEY".to_string(),
+        (false, _) => "id INTEGER PRIMARY KEY AUTOINCREMENT".to_string(),
+    };
+    let mut columns = vec![format!("    {}", id_column)];
+    for field in &spec.fields {
+        columns.push(format!("    {} {} NOT NULL", field.name, field.sql_type(spec.project.database)?));
+    }
+    let timestamp = match spec.project.database {
+        crate::utils::project::Database::Postgres => "TIMESTAMPTZ",
+        _ => "TEXT",
+    };
+    for field in spec.timestamp_fields() {
+        columns.push(format!("    {} {} NOT NULL", field, timestamp));
+    }
+    if spec.soft_delete {
+        columns.push(format!("    deleted_at {}", timestamp));
+    }
+    Ok(format!(
+        "CREATE TABLE IF NOT EXISTS {}s (\n{}\n);\n",
+        spec.name,
+        columns.join(",\n")
+    ))
+}
+
+
+/// src/pagination.rs: page and sort parameters and the `Paginated<T>` envelope shared by every module
+pub fn pagination_template(project: &ProjectConfig) -> String {
+    let schema = if project.openapi { ", utoipa::ToSchema" } else { "" };
+    let order_by = if project.database.is_sql() {
+        r#"
+
+    /// `ORDER BY` clause for `sort`, with the id breaking ties so pages do not overlap
+    pub fn order_by(sort: Option<Sort>) -> String {
+        match sort {
+            Some(sort) => format!("{} {}, id", sort.field, if sort.descending { "DESC" } else { "ASC" }),
+            None => "id".to_string(),
+        }
+    }"#
+    } else {
+        ""
+    };
+    format!(
+        r#"use serde::Serialize;
+
+/// Page size when the client does not ask for one
+pub const DEFAULT_PER_PAGE: u32 = 20;
+/// Largest page a client can ask for
+pub const MAX_PER_PAGE: u32 = 100;
+
+/// The `page` and `per_page` query parameters, clamped to sensible values
+#[derive(Clone, Copy, Debug)]
+pub struct Page {{
+    pub page: u32,
+    pub per_page: u32,
+}}
+
+impl Page {{
+    /// Pages start at 1; `per_page` defaults to `DEFAULT_PER_PAGE` and is capped at `MAX_PER_PAGE`.
+    pub fn new(page: Option<u32>, per_page: Option<u32>) -> Self {{
+        Page {{
+            page: page.unwrap_or(1).max(1),
+            per_page: per_page.unwrap_or(DEFAULT_PER_PAGE).clamp(1, MAX_PER_PAGE),
+        }}
+    }}
+
+    /// Number of items before this page
+    pub fn offset(&self) -> u64 {{
+        u64::from(self.page - 1) * u64::from(self.per_page)
+    }}
+}}
+
+/// The `sort` query parameter: a field, prefixed with `-` for descending order
+#[derive(Clone, Copy, Debug)]
+pub struct Sort {{
+    pub field: &'static str,
+    pub descending: bool,
+}}
+
+impl Sort {{
+    /// Parse `sort`, rejecting fields that are not in `sortable`.
+    pub fn parse(sort: Option<&str>, sortable: &[&'static str]) -> Result<Option<Sort>, String> {{
+        let Some(sort) = sort.filter(|s| !s.is_empty()) else {{
+            return Ok(None);
+        }};
+        let (name, descending) = match sort.strip_prefix('-') {{
+            Some(name) => (name, true),
+            None => (sort, false),
+        }};
+        match sortable.iter().find(|field| **field == name) {{
+            Some(field) => Ok(Some(Sort {{ field, descending }})),
+            None => Err(format!("cannot sort by `{{}}`, expected one of: {{}}", name, sortable.join(", "))),
+        }}
+    }}{order_by}
+}}
+
+/// A page of items with the totals clients need to fetch the others
+#[derive(Serialize{schema})]
+pub struct Paginated<T> {{
+    pub items: Vec<T>,
+    pub page: u32,
+    pub per_page: u32,
+    /// Items matching the filters across all pages
+    pub total: u64,
+    pub total_pages: u64,
+}}
+
+impl<T> Paginated<T> {{
+    pub fn new(items: Vec<T>, page: Page, total: u64) -> Self {{
+        Paginated {{
+            items,
+            page: page.page,
+            per_page: page.per_page,
+            total,
+            total_pages: total.div_ceil(u64::from(page.per_page)),
+        }}
+    }}
+}}
+"#
+    )
+}
+
+
+/// `{Cap}Api` collecting the module's OpenAPI paths and schemas, appended to the module's mod.rs
+pub fn openapi_template(spec: &ModuleSpec) -> String {
+    let name = spec.name.as_str();
+    let cap = capitalize(name);
+    format!(
+        r#"
+#[derive(utoipa::OpenApi)]
+#[openapi(
+    paths({name}_controller::create, {name}_controller::find_all, {name}_controller::find_one, {name}_controller::update, {name}_controller::remove),
+    components(schemas(dto::{name}_dto::Create{cap}Dto, dto::{name}_dto::Update{cap}Dto, entities::{name}_entity::{cap}))
+)]
+pub struct {cap}Api;
+"#
+    )
+}
+
+
+//===================================tests==========================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::project::Database;
+
+    fn spec(database: Database) -> ModuleSpec {
+        let project = ProjectConfig { database, ..ProjectConfig::default() };
+        ModuleSpec {
+            name: "hero".to_string(),
+            fields: vec![Field::new("name", "String"), Field::new("age", "i32")],
+            id: project.id_strategy(),
+            project,
+            permissions: false,
+            version: None,
+            timestamps: false,
+            soft_delete: false,
+        }
+    }
+
+    #[test]
+    fn test_in_memory_service_uses_fields() {
+        let service = service_template(&spec(Database::None));
+        assert!(service.contains("Hero { id, name: dto.name, age: dto.age }"));
+        assert!(service.contains("if let Some(age) = dto.age {"));
+        assert!(service.contains("static HEROS: Lazy<RwLock<Vec<Hero>>> = Lazy::new(|| RwLock::new(Vec::new()));"));
+        assert!(service.contains("pub async fn update(id: u32, dto: UpdateHeroDto) -> String {\n    let mut heros = HEROS.write().await;"));
+        assert!(controller_template(&spec(Database::None)).contains("hero_service::find_one(id).await;"));
+    }
+
+    #[test]
+    fn test_sql_service_binds_fields_in_order() {
+        let service = service_template(&spec(Database::Postgres));
+        assert!(service.contains("INSERT INTO heros (name, age) VALUES ($1, $2) RETURNING id"));
+        assert!(service.contains("UPDATE heros SET name = COALESCE($1, name), age = COALESCE($2, age) WHERE id = $3"));
+        assert!(controller_template(&spec(Database::Postgres)).contains("hero_service::create(dto).await"));
+    }
+
+    #[test]
+    fn test_ids_follow_the_id_strategy() {
+        let counted = service_template(&spec(Database::None));
+        assert!(counted.contains("static NEXT_ID: AtomicU32 = AtomicU32::new(1);"));
+        assert!(counted.contains("let id = NEXT_ID.fetch_add(1, atomic::Ordering::Relaxed);"));
+        assert!(!counted.contains("len() + 1"));
+
+        let mut uuid = spec(Database::Sqlite);
+        uuid.id = IdStrategy::Uuid;
+        let service = service_template(&uuid);
+        assert!(service.contains("INSERT INTO heros (id, name, age) VALUES ($1, $2, $3)"));
+        assert!(service.contains("pub async fn remove(id: uuid::Uuid) -> String {"));
+        assert!(migration_template(&uuid).unwrap().contains("id BLOB PRIMARY KEY"));
+        assert!(entity_template(&uuid).contains("pub id: uuid::Uuid,"));
+        assert!(controller_template(&uuid).contains("pub async fn find_one(Path(id): Path<uuid::Uuid>)"));
+
+        let mut ulid = spec(Database::None);
+        ulid.id = IdStrategy::Ulid;
+        assert!(service_template(&ulid).contains("let id = ulid::Ulid::new();"));
+        assert!(IdStrategy::Ulid.check(Database::Sqlite).is_err());
+        assert!(IdStrategy::U32.check(Database::Postgres).is_err());
+    }
+
+    #[test]
+    fn test_services_are_instrumented_with_tracing() {
+        let mut traced = spec(Database::Sqlite);
+        traced.project.tracing = true;
+        let service = service_template(&traced);
+        assert!(service.contains("#[tracing::instrument(skip(dto))]\npub async fn create("));
+        assert!(service.contains("#[tracing::instrument]\npub async fn find_one("));
+        assert!(!service_template(&spec(Database::None)).contains("tracing::"));
+    }
+
+    #[test]
+    fn test_find_all_filters_sorts_and_paginates() {
+        let mut paged = spec(Database::None);
+        paged.fields.push(Field::new("page", "i32"));
+        let dto = dto_template(&paged);
+        assert!(dto.contains("pub struct HeroListQuery {"));
+        assert!(dto.contains("    pub age: Option<i32>,\n}"));
+        assert_eq!(dto.matches("pub page: Option<i32>,").count(), 1);
+        assert!(dto.contains("&[\"id\", \"name\", \"age\", \"page\"]"));
+
+        let service = service_template(&paged);
+        assert!(service.contains("pub async fn find_all(query: &HeroListQuery, sort: Option<Sort>) -> Paginated<Hero> {"));
+        assert!(service.contains(".filter(|item| query.age.as_ref().is_none_or(|value| item.age == *value))"));
+        assert!(service.contains("\"page\" => a.page.partial_cmp(&b.page),"));
+        assert!(!service.contains("query.page.as_ref()"));
+
+        let sql = service_template(&spec(Database::Sqlite));
+        assert!(sql.contains("const FILTERS: &str = \"($1 IS NULL OR name = $1) AND ($2 IS NULL OR age = $2)\";"));
+        assert!(sql.contains("ORDER BY {} LIMIT $3 OFFSET $4"));
+        assert!(controller_template(&spec(Database::Sqlite)).contains(".await.map_err(|err| (StatusCode::INTERNAL_SERVER_ERROR, err))?;"));
+        assert!(pagination_template(&ProjectConfig { database: Database::Sqlite, ..ProjectConfig::default() }).contains("pub fn order_by("));
+        assert!(!pagination_template(&ProjectConfig::default()).contains("pub fn order_by("));
+    }
+
+    #[test]
+    fn test_timestamps_and_soft_delete() {
+        let mut stamped = spec(Database::None);
+        stamped.timestamps = true;
+        stamped.soft_delete = true;
+        let service = service_template(&stamped);
+        assert!(service.contains("created_at: now, updated_at: now, deleted_at: None"));
+        assert!(service.contains("hero.updated_at = chrono::Utc::now();"));
+        assert!(service.contains("hero.deleted_at = Some(chrono::Utc::now());"));
+        assert!(service.contains(".filter(|item| item.deleted_at.is_none())"));
+        assert!(entity_template(&stamped).contains("pub deleted_at: Option<chrono::DateTime<chrono::Utc>>,"));
+        assert!(dto_template(&stamped).contains("\"created_at\", \"updated_at\""));
+
+        stamped.project.database = Database::Postgres;
+        stamped.id = IdStrategy::I64;
+        let sql = service_template(&stamped);
+        assert!(sql.contains("INSERT INTO heros (name, age, created_at, updated_at) VALUES ($1, $2, $3, $4)"));
+        assert!(sql.contains("updated_at = $3 WHERE id = $4 AND deleted_at IS NULL"));
+        assert!(sql.contains("UPDATE heros SET deleted_at = $1 WHERE id = $2 AND deleted_at IS NULL"));
+        assert!(sql.contains("const FILTERS: &str = \"deleted_at IS NULL AND "));
+        let migration = migration_template(&stamped).unwrap();
+        assert!(migration.contains("created_at TIMESTAMPTZ NOT NULL"));
+        assert!(migration.contains("    deleted_at TIMESTAMPTZ\n"));
+    }
+
+    #[test]
+    fn test_migration_maps_field_types() {
+        let migration = migration_template(&spec(Database::Sqlite)).unwrap();
+        assert!(migration.contains("id INTEGER PRIMARY KEY AUTOINCREMENT"));
+        assert!(migration.contains("age INTEGER NOT NULL"));
+
+        let mut unsupported = spec(Database::Postgres);
+        unsupported.fields.push(Field::new("tags", "Vec<String>"));
+        assert!(migration_template(&unsupported).is_err());
+    }
+}
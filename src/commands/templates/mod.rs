@@ -1,3 +1,9 @@
+pub mod dependencies;
 pub mod generate_module_templates;
 pub mod new_templates;
 pub mod generate_item_templates;
+pub mod auth_templates;
+pub mod rbac_templates;
+pub mod route_templates;
+pub mod relation_templates;
+pub mod version_templates;
@@ -0,0 +1,184 @@
+use crate::utils::common::capitalize;
+use crate::utils::project::{Database, ProjectConfig};
+
+/// CRUD permissions of a generated module, with the handlers they guard
+pub const CRUD_PERMISSIONS: [(&str, &[&str]); 4] = [
+    ("create", &["create"]),
+    ("read", &["find_all", "find_one"]),
+    ("update", &["update"]),
+    ("delete", &["remove"]),
+];
+
+/// Type of user ids, matching the auth module's
+fn id_type(project: &ProjectConfig) -> &'static str {
+    if project.database.is_sql() { "i64" } else { "u32" }
+}
+
+/// src/rbac/mod.rs: roles, permissions and the `RequirePermission` extractor
+pub fn rbac_mod_template(project: &ProjectConfig) -> String {
+    let (error_use, forbidden) = if project.error_handling {
+        ("use crate::error::AppError;", "AppError::Forbidden(format!(\"missing permission `{}`\", permission))")
+    } else {
+        (
+            "use axum::http::StatusCode;",
+            "(StatusCode::FORBIDDEN, format!(\"missing permission `{}`\", permission))",
+        )
+    };
+    format!(
+        r#"pub mod role_store;
+
+use axum::extract::FromRequestParts;
+use axum::http::request::Parts;
+use std::marker::PhantomData;
+
+use crate::auth::auth_service::AuthError;
+use crate::auth::current_user::CurrentUser;
+use crate::config::CONFIG;
+{error_use}
+
+/// Roles and the permissions they grant.
+/// `*` grants every permission and `hero:*` every permission on `hero`.
+pub const ROLES: &[(&str, &[&str])] = &[
+    ("admin", &["*"]),
+    ("user", &[]),
+];
+
+/// Role every signed-in user has
+pub const DEFAULT_ROLE: &str = "user";
+
+/// A permission handlers can require, such as `hero:create`
+pub trait Permission {{
+    const NAME: &'static str;
+}}
+
+/// Whether any of `roles` grants `permission`.
+pub fn has_permission(roles: &[String], permission: &str) -> bool {{
+    let resource = permission.split(':').next().unwrap_or(permission);
+    ROLES
+        .iter()
+        .filter(|(role, _)| roles.iter().any(|r| r == role))
+        .flat_map(|(_, granted)| granted.iter())
+        .any(|granted| *granted == "*" || *granted == permission || granted.strip_suffix(":*") == Some(resource))
+}}
+
+/// Roles of `user`: the default role, `admin` for the configured `admin_email`, and those in the role store.
+pub async fn roles_of(user: &CurrentUser) -> Result<Vec<String>, AuthError> {{
+    let mut roles = vec![DEFAULT_ROLE.to_string()];
+    if !CONFIG.admin_email.is_empty() && user.email == CONFIG.admin_email {{
+        roles.push("admin".to_string());
+    }}
+    roles.extend(role_store::roles_of(user.id).await?);
+    Ok(roles)
+}}
+
+/// Enforces permission `P`: add `_permission: RequirePermission<HeroCreate>` to a handler's parameters.
+/// Rejects with 401 when the caller is not signed in and 403 when none of their roles grants `P`.
+pub struct RequirePermission<P: Permission>(PhantomData<fn() -> P>);
+
+impl<P: Permission, S: Send + Sync> FromRequestParts<S> for RequirePermission<P> {{
+    type Rejection = AuthError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {{
+        let user = CurrentUser::from_request_parts(parts, state).await?;
+        if has_permission(&roles_of(&user).await?, P::NAME) {{
+            Ok(RequirePermission(PhantomData))
+        }} else {{
+            Err(forbidden(P::NAME))
+        }}
+    }}
+}}
+
+fn forbidden(permission: &str) -> AuthError {{
+    {forbidden}
+}}
+"#
+    )
+}
+
+/// src/rbac/role_store.rs: roles assigned to users on top of the default one
+pub fn role_store_template(project: &ProjectConfig) -> String {
+    let id = id_type(project);
+    if project.database.is_sql() {
+        format!(
+            r#"use crate::auth::auth_service::{{internal, AuthError}};
+use crate::db;
+
+/// Roles given to `user_id` with `assign`
+pub async fn roles_of(user_id: {id}) -> Result<Vec<String>, AuthError> {{
+    sqlx::query_scalar::<_, String>("SELECT role FROM user_roles WHERE user_id = $1")
+        .bind(user_id)
+        .fetch_all(db::pool())
+        .await
+        .map_err(internal)
+}}
+
+/// Give `role` to `user_id`; call it from an admin endpoint or a seed script.
+#[allow(dead_code)]
+pub async fn assign(user_id: {id}, role: &str) -> Result<(), AuthError> {{
+    sqlx::query("INSERT INTO user_roles (user_id, role) VALUES ($1, $2) ON CONFLICT DO NOTHING")
+        .bind(user_id)
+        .bind(role)
+        .execute(db::pool())
+        .await
+        .map_err(internal)?;
+    Ok(())
+}}
+"#
+        )
+    } else {
+        format!(
+            r#"use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+use crate::auth::auth_service::AuthError;
+
+/// Roles of this process, lost on restart
+static ASSIGNMENTS: Lazy<RwLock<HashMap<{id}, Vec<String>>>> = Lazy::new(|| RwLock::new(HashMap::new()));
+
+/// Roles given to `user_id` with `assign`
+pub async fn roles_of(user_id: {id}) -> Result<Vec<String>, AuthError> {{
+    Ok(ASSIGNMENTS.read().unwrap().get(&user_id).cloned().unwrap_or_default())
+}}
+
+/// Give `role` to `user_id`; call it from an admin endpoint or a seed script.
+#[allow(dead_code)]
+pub async fn assign(user_id: {id}, role: &str) -> Result<(), AuthError> {{
+    let mut assignments = ASSIGNMENTS.write().unwrap();
+    let roles = assignments.entry(user_id).or_default();
+    if !roles.iter().any(|r| r == role) {{
+        roles.push(role.to_string());
+    }}
+    Ok(())
+}}
+"#
+        )
+    }
+}
+
+/// migrations/{version}_create_user_roles.sql
+pub fn user_roles_migration_template(database: Database) -> String {
+    let integer = match database {
+        Database::Postgres => "BIGINT",
+        _ => "INTEGER",
+    };
+    format!(
+        "CREATE TABLE IF NOT EXISTS user_roles (\n    user_id {} NOT NULL REFERENCES users (id) ON DELETE CASCADE,\n    role TEXT NOT NULL,\n    PRIMARY KEY (user_id, role)\n);\n",
+        integer
+    )
+}
+
+/// src/{module}/{module}_permissions.rs: one marker type per CRUD permission
+pub fn permissions_template(name: &str) -> String {
+    let cap = capitalize(name);
+    let types: String = CRUD_PERMISSIONS
+        .iter()
+        .map(|(action, _)| {
+            format!(
+                "\n/// `{name}:{action}`\npub struct {cap}{Action};\n\nimpl Permission for {cap}{Action} {{\n    const NAME: &'static str = \"{name}:{action}\";\n}}\n",
+                Action = capitalize(action)
+            )
+        })
+        .collect();
+    format!("use crate::rbac::Permission;\n{}", types)
+}
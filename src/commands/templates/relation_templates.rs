@@ -0,0 +1,168 @@
+use crate::utils::common::capitalize;
+use crate::utils::project::{Database, IdStrategy};
+
+/// A one-to-many relation: every `child` belongs to one `parent` through `{parent}_id`
+pub struct Relation {
+    pub parent: String,
+    pub child: String,
+    /// Id strategy of the parent, which the foreign key follows
+    pub id: IdStrategy,
+    pub database: Database,
+    /// Whether soft-deleted rows (with `deleted_at`) of either side must be skipped
+    pub parent_soft_delete: bool,
+    pub child_soft_delete: bool,
+}
+
+impl Relation {
+    /// The foreign key column and field on the child, e.g. `hero_id`
+    pub fn foreign_key(&self) -> String {
+        format!("{}_id", self.parent)
+    }
+
+    /// Type of the foreign key on the child entity. In SQL tables the column added to existing rows has to be nullable.
+    pub fn foreign_key_type(&self) -> String {
+        let id = self.id.rust_type();
+        if self.database.is_sql() { format!("Option<{}>", id) } else { id.to_string() }
+    }
+
+    /// The parent's handler and service function listing its children, e.g. `find_powers`
+    pub fn finder(&self) -> String {
+        format!("find_{}s", self.child)
+    }
+
+    /// `#[tracing::instrument]` line for service functions, empty without tracing
+    fn span(tracing: bool) -> &'static str {
+        if tracing { "#[tracing::instrument]\n" } else { "" }
+    }
+}
+
+/// `find_by_{parent}` appended to the child's service
+pub fn child_finder_template(relation: &Relation, tracing: bool, is_async: bool) -> String {
+    let Relation { parent, child, .. } = relation;
+    let id = relation.id.rust_type();
+    let cap = capitalize(child);
+    let span = Relation::span(tracing);
+    if relation.database.is_sql() {
+        let mut live = String::new();
+        if relation.child_soft_delete {
+            live.push_str(&format!(" AND {child}s.deleted_at IS NULL"));
+        }
+        if relation.parent_soft_delete {
+            live.push_str(&format!(" AND {parent}s.deleted_at IS NULL"));
+        }
+        format!(
+            r#"
+/// {cap}s belonging to the {parent} `{parent}_id`, joined through `{parent}s`
+{span}pub async fn find_by_{parent}({parent}_id: {id}) -> Result<Vec<{cap}>, String> {{
+    sqlx::query_as::<_, {cap}>(
+        "SELECT {child}s.* FROM {child}s JOIN {parent}s ON {parent}s.id = {child}s.{parent}_id WHERE {parent}s.id = $1{live} ORDER BY {child}s.id",
+    )
+    .bind({parent}_id)
+    .fetch_all(db::pool())
+    .await
+    .map_err(|err| format!("Failed to list the {child}s of {parent} {{}}: {{}}", {parent}_id, err))
+}}
+"#
+        )
+    } else {
+        // async in-memory services keep their store in a tokio `RwLock`, older sync ones in a `Mutex`
+        let (asyncness, store) = if is_async { ("async ", "read().await") } else { ("", "lock().unwrap()") };
+        let upp = child.to_uppercase();
+        let live = if relation.child_soft_delete { " && item.deleted_at.is_none()" } else { "" };
+        format!(
+            r#"
+/// {cap}s belonging to the {parent} `{parent}_id`
+{span}pub {asyncness}fn find_by_{parent}({parent}_id: {id}) -> Vec<{cap}> {{
+    let {child}s = {upp}S.{store};
+    {child}s.iter().filter(|item| item.{parent}_id == {parent}_id{live}).cloned().collect()
+}}
+"#
+        )
+    }
+}
+
+/// `find_{child}s` appended to the parent's service
+pub fn parent_finder_template(relation: &Relation, tracing: bool, is_async: bool) -> String {
+    let Relation { parent, child, .. } = relation;
+    let id = relation.id.rust_type();
+    let cap = capitalize(child);
+    let finder = relation.finder();
+    let span = Relation::span(tracing);
+    if relation.database.is_sql() {
+        let live = if relation.parent_soft_delete { " AND deleted_at IS NULL" } else { "" };
+        format!(
+            r#"
+/// {cap}s of the {parent} `id`, or `None` when there is no such {parent}
+{span}pub async fn {finder}(id: {id}) -> Result<Option<Vec<{cap}>>, String> {{
+    let found = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM {parent}s WHERE id = $1{live}")
+        .bind(id)
+        .fetch_one(db::pool())
+        .await
+        .map_err(|err| format!("Failed to find {parent}: {{}}", err))?;
+    if found == 0 {{
+        return Ok(None);
+    }}
+    {child}_service::find_by_{parent}(id).await.map(Some)
+}}
+"#
+        )
+    } else {
+        let (asyncness, awaited, store) =
+            if is_async { ("async ", ".await", "read().await") } else { ("", "", "lock().unwrap()") };
+        let upp = parent.to_uppercase();
+        let live = if relation.parent_soft_delete { " && item.deleted_at.is_none()" } else { "" };
+        format!(
+            r#"
+/// {cap}s of the {parent} `id`, or `None` when there is no such {parent}
+{span}pub {asyncness}fn {finder}(id: {id}) -> Option<Vec<{cap}>> {{
+    if !{upp}S.{store}.iter().any(|item| item.id == id{live}) {{
+        return None;
+    }}
+    Some({child}_service::find_by_{parent}(id){awaited})
+}}
+"#
+        )
+    }
+}
+
+/// `GET /{parent}/{id}/{child}s` handler appended to the parent's controller
+pub fn relation_handler_template(relation: &Relation, openapi: bool, is_async: bool) -> String {
+    let Relation { parent, child, .. } = relation;
+    let id = relation.id.rust_type();
+    let cap = capitalize(child);
+    let parent_cap = capitalize(parent);
+    let finder = relation.finder();
+    let doc = if openapi {
+        format!(
+            "#[utoipa::path(get, path = \"/{{id}}/{child}s\", tag = \"{parent}\", params((\"id\" = {id}, Path)), responses((status = 200, body = Vec<{cap}>), (status = 404, body = String)))]\n"
+        )
+    } else {
+        String::new()
+    };
+    // the SQL service reports database errors on top of a missing parent
+    let found = match (relation.database.is_sql(), is_async) {
+        (true, _) => ".await.map_err(|err| (StatusCode::INTERNAL_SERVER_ERROR, err))?",
+        (false, true) => ".await",
+        (false, false) => "",
+    };
+    format!(
+        r#"
+// GET /{parent}/{{id}}/{child}s
+{doc}pub async fn {finder}(Path(id): Path<{id}>) -> Result<Json<Vec<{cap}>>, (StatusCode, String)> {{
+    match {parent}_service::{finder}(id){found} {{
+        Some({child}s) => Ok(Json({child}s)),
+        None => Err((StatusCode::NOT_FOUND, format!("{parent_cap} with id {{}} not found", id))),
+    }}
+}}
+"#
+    )
+}
+
+/// migrations/{version}_add_{parent}_id_to_{child}s.sql
+pub fn relation_migration_template(relation: &Relation) -> String {
+    let Relation { parent, child, .. } = relation;
+    let column = relation.id.sql_type(relation.database);
+    format!(
+        "ALTER TABLE {child}s ADD COLUMN {parent}_id {column} REFERENCES {parent}s (id) ON DELETE CASCADE;\nCREATE INDEX IF NOT EXISTS {child}s_{parent}_id ON {child}s ({parent}_id);\n"
+    )
+}
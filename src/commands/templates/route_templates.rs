@@ -0,0 +1,133 @@
+/// A custom endpoint added to an existing module by `generate route`
+pub struct Endpoint {
+    pub module: String,
+    pub action: String,
+    /// Lowercase axum method router, e.g. `post`
+    pub method: &'static str,
+    /// Path under the module's prefix, e.g. `/{id}/publish`
+    pub path: String,
+    /// Path parameters and their Rust types, in order
+    pub params: Vec<(String, String)>,
+    pub body: Option<String>,
+    pub query: Option<String>,
+}
+
+impl Endpoint {
+    /// `Path(..)` extractor binding the path parameters, if there are any
+    fn path_extractor(&self) -> Option<String> {
+        match self.params.as_slice() {
+            [] => None,
+            [(name, ty)] => Some(format!("Path({}): Path<{}>", name, ty)),
+            params => {
+                let names = params.iter().map(|(name, _)| name.as_str()).collect::<Vec<_>>().join(", ");
+                let types = params.iter().map(|(_, ty)| ty.as_str()).collect::<Vec<_>>().join(", ");
+                Some(format!("Path(({})): Path<({})>", names, types))
+            }
+        }
+    }
+
+    /// Names the handler passes on to the service: path parameters, then `dto` and `query`
+    fn arguments(&self) -> Vec<&str> {
+        let mut args: Vec<&str> = self.params.iter().map(|(name, _)| name.as_str()).collect();
+        if self.body.is_some() {
+            args.push("dto");
+        }
+        if self.query.is_some() {
+            args.push("query");
+        }
+        args
+    }
+}
+
+/// Handler appended to src/{module}/{module}_controller.rs.
+/// `doc` is the `#[utoipa::path]` attribute in OpenAPI projects, empty otherwise.
+pub fn route_handler_template(endpoint: &Endpoint, doc: &str, awaited: bool) -> String {
+    let Endpoint { module, action, method, path, .. } = endpoint;
+    let mut params: Vec<String> = endpoint.path_extractor().into_iter().collect();
+    if let Some(query) = &endpoint.query {
+        params.push(format!("Query(query): Query<{}>", query));
+    }
+    if let Some(body) = &endpoint.body {
+        // the body extractor consumes the request, so it comes last
+        params.push(format!("Json(dto): Json<{}>", body));
+    }
+    let awaited = if awaited { ".await" } else { "" };
+    format!(
+        r#"
+// {METHOD} /{module}{path}
+{doc}pub async fn {action}({params}) -> (StatusCode, String) {{
+    let message = {module}_service::{action}({args}){awaited};
+    (StatusCode::OK, message)
+}}
+"#,
+        METHOD = method.to_uppercase(),
+        params = params.join(", "),
+        args = endpoint.arguments().join(", "),
+    )
+}
+
+/// Stub appended to src/{module}/{module}_service.rs for the handler to call
+pub fn route_service_template(endpoint: &Endpoint, tracing: bool, is_async: bool) -> String {
+    let Endpoint { module, action, .. } = endpoint;
+    let mut params: Vec<String> = endpoint.params.iter().map(|(name, ty)| format!("{}: {}", name, ty)).collect();
+    let mut skipped = Vec::new();
+    if let Some(body) = &endpoint.body {
+        params.push(format!("_dto: {}", body));
+        skipped.push("_dto");
+    }
+    if let Some(query) = &endpoint.query {
+        params.push(format!("_query: {}", query));
+        skipped.push("_query");
+    }
+    let span = match (tracing, skipped.is_empty()) {
+        (false, _) => String::new(),
+        (true, true) => "#[tracing::instrument]\n".to_string(),
+        (true, false) => format!("#[tracing::instrument(skip({}))]\n", skipped.join(", ")),
+    };
+    let asyncness = if is_async { "async " } else { "" };
+    let message = if endpoint.params.is_empty() {
+        format!("\"{} {}: not implemented yet\".to_string()", action, module)
+    } else {
+        let placeholders: String = endpoint.params.iter().map(|_| " {}").collect();
+        let values: String = endpoint.params.iter().map(|(name, _)| format!(", {}", name)).collect();
+        format!("format!(\"{} {}{}: not implemented yet\"{})", action, module, placeholders, values)
+    };
+    format!(
+        r#"
+{span}pub {asyncness}fn {action}({params}) -> String {{
+    // TODO: implement `{action}`
+    {message}
+}}
+"#,
+        params = params.join(", "),
+    )
+}
+
+/// Empty `Deserialize` struct appended to the module's DTO file for a new `--body` or `--query` type
+pub fn route_dto_template(name: &str, action: &str, is_query: bool, openapi: bool) -> String {
+    let (derives, what) = match (is_query, openapi) {
+        (false, false) => ("Deserialize", "JSON body"),
+        (false, true) => ("Deserialize, utoipa::ToSchema", "JSON body"),
+        (true, false) => ("Deserialize", "Query string"),
+        (true, true) => ("Deserialize, utoipa::IntoParams)]\n#[into_params(parameter_in = Query", "Query string"),
+    };
+    format!("\n/// {what} of `{action}`\n#[derive({derives})]\npub struct {name} {{\n}}\n")
+}
+
+
+/// `#[utoipa::path]` of the handler; the query and body are documented when they derive `IntoParams` and `ToSchema`
+pub fn route_doc_template(endpoint: &Endpoint, document_query: bool, document_body: bool) -> String {
+    let mut params: Vec<String> = endpoint.params.iter().map(|(name, ty)| format!("(\"{}\" = {}, Path)", name, ty)).collect();
+    if let Some(query) = endpoint.query.as_ref().filter(|_| document_query) {
+        params.push(query.clone());
+    }
+    let mut doc = format!("#[utoipa::path({}, path = \"{}\", tag = \"{}\"", endpoint.method, endpoint.path, endpoint.module);
+    if !params.is_empty() {
+        doc.push_str(&format!(", params({})", params.join(", ")));
+    }
+    if let Some(body) = endpoint.body.as_ref().filter(|_| document_body) {
+        doc.push_str(&format!(", request_body = {}", body));
+    }
+    doc.push_str(", responses((status = 200, body = String)))]\n");
+    doc
+}
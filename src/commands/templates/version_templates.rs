@@ -0,0 +1,27 @@
+/// Conversions appended to a DTO file copied by `version bump`, from each of its structs into the
+/// struct of the same name in `source` (e.g. `crate::hero::dto::hero_dto`), which the shared service takes.
+/// `structs` are the struct names with their field names.
+pub fn dto_conversions_template(source: &str, structs: &[(String, Vec<String>)]) -> String {
+    let mut conversions = format!(
+        "\n// The service is shared with `{}`, so requests convert into its DTOs.\n// Map here any field this version adds, renames or drops.\n",
+        source.split("::dto::").next().unwrap_or(source)
+    );
+    for (name, fields) in structs {
+        let (arg, body) = if fields.is_empty() {
+            ("_dto", "Self {}".to_string())
+        } else {
+            let assignments: String = fields.iter().map(|field| format!("            {0}: dto.{0},\n", field)).collect();
+            ("dto", format!("Self {{\n{}        }}", assignments))
+        };
+        conversions.push_str(&format!(
+            r#"
+impl From<{name}> for {source}::{name} {{
+    fn from({arg}: {name}) -> Self {{
+        {body}
+    }}
+}}
+"#
+        ));
+    }
+    conversions
+}
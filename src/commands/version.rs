@@ -0,0 +1,248 @@
+use anyhow::{bail, Context, Result};
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+use syn::visit::{self, Visit};
+use syn::{Expr, ExprCall, Fields, FnArg, Item, Pat};
+
+use crate::utils::ast::*;
+use crate::utils::file::{ensure_dir, module_dir};
+use crate::utils::project::ProjectConfig;
+
+use super::generate_module::{declare_versioned_module, mount_module};
+use super::templates::version_templates::dto_conversions_template;
+
+/// Accept API versions written `v` and a number, e.g. `v2`.
+pub fn parse_version(version: &str) -> Result<String, String> {
+    match version.strip_prefix('v') {
+        Some(number) if !number.is_empty() && number.chars().all(|c| c.is_ascii_digit()) => Ok(version.to_string()),
+        _ => Err(format!("`{}` is not a version such as `v2`", version)),
+    }
+}
+
+/// Copy the controller and DTOs of `module` (the unversioned one, or the one of version `from`) to src/{version}/{module}
+/// and serve them under `/{version}/{module}`. The new version shares the service and entities of the one it was copied from.
+pub fn bump_version(module: String, version: String, from: Option<String>) -> Result<()> {
+    println!("Bumping module {} to {}", module, version);
+    if from.as_deref() == Some(version.as_str()) {
+        bail!("`{}` is already at {}", module, version);
+    }
+    let project = ProjectConfig::load()?;
+    let (source_dir, source_path) = match &from {
+        Some(from) => (module_dir(from).join(&module), format!("{}::{}", from, module)),
+        None => (module_dir(&module), module.clone()),
+    };
+    let target_dir = module_dir(&version).join(&module);
+    let target_path = format!("{}::{}", version, module);
+    if target_dir.exists() {
+        bail!("{} already exists", target_dir.to_string_lossy());
+    }
+
+    let mod_path = source_dir.join("mod.rs");
+    let mod_rs = fs::read_to_string(&mod_path)
+        .with_context(|| format!("Module `{}` not found at {}", module, source_dir.to_string_lossy()))?;
+    let controller_mod = format!("{}_controller", module);
+    let controller_path = source_dir.join(format!("{}.rs", controller_mod));
+    let controller = fs::read_to_string(&controller_path).with_context(|| format!("{} not found", controller_path.to_string_lossy()))?;
+
+    // 1️⃣ mod.rs: the controller and DTOs are the version's own, the other submodules are the source's
+    let mod_rs = share_submodules(&mod_rs, &mod_path, &source_path, &[controller_mod.as_str(), "dto"])?;
+
+    // 2️⃣ the controller takes the new DTOs and converts them for the shared service
+    let controller = controller.replace(&format!("crate::{}::dto::", source_path), &format!("crate::{}::dto::", target_path));
+    let controller = convert_service_arguments(&controller, &controller_path, &format!("{}_service", module))?;
+
+    // 3️⃣ the DTOs, each with a conversion into the source's
+    let mut dtos = Vec::new();
+    let source_dto_dir = source_dir.join("dto");
+    for entry in fs::read_dir(&source_dto_dir).into_iter().flatten().flatten() {
+        let path = entry.path();
+        let mut content = fs::read_to_string(&path)?;
+        let stem = path.file_stem().and_then(|stem| stem.to_str()).unwrap_or_default().to_string();
+        if stem != "mod" && path.extension().is_some_and(|ext| ext == "rs") {
+            let file = parse_file(&path, &content)?;
+            let structs: Vec<(String, Vec<String>)> = file
+                .items
+                .iter()
+                .filter_map(|item| match item {
+                    Item::Struct(s) if s.generics.params.is_empty() => match &s.fields {
+                        Fields::Named(fields) => {
+                            Some((s.ident.to_string(), fields.named.iter().filter_map(|f| Some(f.ident.as_ref()?.to_string())).collect()))
+                        }
+                        _ => None,
+                    },
+                    _ => None,
+                })
+                .collect();
+            if !structs.is_empty() {
+                content.push_str(&dto_conversions_template(&format!("crate::{}::dto::{}", source_path, stem), &structs));
+            }
+        }
+        dtos.push((entry.file_name(), content));
+    }
+
+    let dto_dir = ensure_dir(&target_dir.join("dto"))?;
+    for (file_name, content) in dtos {
+        fs::write(dto_dir.join(file_name), content)?;
+    }
+    fs::write(target_dir.join("mod.rs"), mod_rs)?;
+    fs::write(target_dir.join(format!("{}.rs", controller_mod)), controller)?;
+
+    // 4️⃣ declare the version namespace and serve the new version next to the old one
+    declare_versioned_module(&version, &module)?;
+    mount_module(&target_path, project.openapi)?;
+
+    println!(
+        "Module {} bumped to {}: src/{}/{} is served under /{}/{} and shares {}_service",
+        module, version, version, module, version, module, module
+    );
+    Ok(())
+}
+
+/// Drop the file-backed `mod` declarations of `mod_rs` other than `own`. The ones mod.rs itself uses, such as
+/// `entities` in the OpenAPI schemas, become re-exports from `crate::{source}` so their paths keep resolving in the copy.
+fn share_submodules(mod_rs: &str, mod_path: &Path, source: &str, own: &[&str]) -> Result<String> {
+    let file = parse_file(mod_path, mod_rs)?;
+    let mut shared: Vec<(std::ops::Range<usize>, String)> = file
+        .items
+        .iter()
+        .filter_map(|item| match item {
+            Item::Mod(m) if m.content.is_none() && !own.contains(&m.ident.to_string().as_str()) => {
+                let mut range = byte_range(m);
+                if mod_rs.contains(&format!("{}::", m.ident)) {
+                    Some((range, format!("pub use crate::{}::{};", source, m.ident)))
+                } else {
+                    range.end += mod_rs[range.end..].starts_with('\n') as usize;
+                    Some((range, String::new()))
+                }
+            }
+            _ => None,
+        })
+        .collect();
+    // splice from the end, so the earlier ranges stay valid
+    shared.sort_by_key(|(range, _)| std::cmp::Reverse(range.start));
+    let mut content = mod_rs.to_string();
+    for (range, reexport) in shared {
+        content.replace_range(range, &reexport);
+    }
+    Ok(content)
+}
+
+/// Add `.into()` to the `Json(..)` and `Query(..)` values the handlers of `controller` pass to `service`.
+fn convert_service_arguments(controller: &str, path: &Path, service: &str) -> Result<String> {
+    let file = parse_file(path, controller)?;
+    let mut ends = Vec::new();
+    for item in &file.items {
+        let Item::Fn(handler) = item else { continue };
+        let bound = handler
+            .sig
+            .inputs
+            .iter()
+            .filter_map(|input| match input {
+                FnArg::Typed(typed) => match &*typed.pat {
+                    Pat::TupleStruct(extractor)
+                        if extractor.path.segments.last().is_some_and(|s| s.ident == "Json" || s.ident == "Query") =>
+                    {
+                        match extractor.elems.first() {
+                            Some(Pat::Ident(binding)) => Some(binding.ident.to_string()),
+                            _ => None,
+                        }
+                    }
+                    _ => None,
+                },
+                FnArg::Receiver(_) => None,
+            })
+            .collect();
+        ServiceCalls { service, bound, ends: &mut ends }.visit_block(&handler.block);
+    }
+    ends.sort_unstable_by(|a, b| b.cmp(a));
+    let mut content = controller.to_string();
+    for end in ends {
+        content.insert_str(end, ".into()");
+    }
+    Ok(content)
+}
+
+/// Finds the arguments of `{service}::..(..)` calls that are one of the `bound` variables, or a reference to one.
+struct ServiceCalls<'a> {
+    service: &'a str,
+    bound: HashSet<String>,
+    ends: &'a mut Vec<usize>,
+}
+
+impl<'ast> Visit<'ast> for ServiceCalls<'_> {
+    fn visit_expr_call(&mut self, call: &'ast ExprCall) {
+        let to_service = matches!(&*call.func, Expr::Path(func) if func.path.segments.first().is_some_and(|s| s.ident == self.service));
+        if to_service {
+            for arg in &call.args {
+                let value = match arg {
+                    Expr::Reference(reference) => &*reference.expr,
+                    other => other,
+                };
+                if let Expr::Path(variable) = value
+                    && variable.path.get_ident().is_some_and(|ident| self.bound.contains(&ident.to_string()))
+                {
+                    self.ends.push(byte_range(value).end);
+                }
+            }
+        }
+        visit::visit_expr_call(self, call);
+    }
+}
+
+//===================================tests==========================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::generate_module::{generate_module, ModuleOptions};
+    use crate::utils::testing::in_temp_project;
+
+    #[test]
+    fn test_parse_version_accepts_v_and_a_number() {
+        assert_eq!(parse_version("v2").unwrap(), "v2");
+        assert!(parse_version("2").is_err());
+        assert!(parse_version("v").is_err());
+        assert!(parse_version("v2beta").is_err());
+    }
+
+    #[test]
+    fn test_bump_version_copies_controller_and_dtos_and_shares_the_service() {
+        in_temp_project("version", |root| {
+            fs::write(root.join("src/main.rs"), "use axum::Router;\n\nasync fn main() {\n    let app = Router::new();\n}\n").unwrap();
+            generate_module(ModuleOptions { name: "hero".to_string(), fields: vec![], permissions: false, version: None, id: None, timestamps: false, soft_delete: false }).unwrap();
+            let villain = ModuleOptions { name: "villain".to_string(), fields: vec![], permissions: false, version: Some("v2".to_string()), id: None, timestamps: false, soft_delete: false };
+            generate_module(villain).unwrap();
+
+            bump_version("hero".to_string(), "v2".to_string(), None).unwrap();
+            assert!(bump_version("hero".to_string(), "v2".to_string(), None).is_err());
+
+            let src = root.join("src");
+            let files = ["v2/hero/mod.rs", "v2/hero/hero_controller.rs", "v2/hero/dto/hero_dto.rs", "v2/villain/villain_service.rs"].map(|file| {
+                let content = fs::read_to_string(src.join(file)).unwrap();
+                syn::parse_file(&content).unwrap_or_else(|err| panic!("{} does not parse: {}", file, err));
+                content
+            });
+            let [mod_rs, controller, dto, villain_service] = files;
+
+            assert!(mod_rs.contains("pub mod hero_controller;") && mod_rs.contains("pub mod dto;"));
+            assert!(!mod_rs.contains("hero_service") && !mod_rs.contains("pub mod entities;"));
+            assert!(controller.contains("use crate::v2::hero::dto::hero_dto::{CreateHeroDto, HeroListQuery, UpdateHeroDto};"));
+            assert!(controller.contains("use crate::hero::hero_service;"));
+            assert!(controller.contains("hero_service::create(dto.into())"));
+            assert!(controller.contains("hero_service::find_all(&query.into(), sort)"));
+            assert!(controller.contains("hero_service::update(id, dto.into())"));
+            assert!(dto.contains("impl From<CreateHeroDto> for crate::hero::dto::hero_dto::CreateHeroDto {"));
+            assert!(villain_service.contains("use crate::v2::villain::entities::villain_entity::Villain;"));
+            assert!(!src.join("v2/hero/hero_service.rs").exists());
+
+            let versions = fs::read_to_string(src.join("v2/mod.rs")).unwrap();
+            assert!(versions.contains("pub mod villain;") && versions.contains("pub mod hero;"));
+            assert!(fs::read_to_string(src.join("lib.rs")).unwrap().contains("pub mod v2;"));
+            let main_rs = fs::read_to_string(src.join("main.rs")).unwrap();
+            assert_eq!(main_rs.matches("mod v2;").count(), 1);
+            assert!(main_rs.contains(".nest(\"/v2/hero\", v2::hero::routes())"));
+            assert!(main_rs.contains(".nest(\"/hero\", hero::routes())"));
+        });
+    }
+}
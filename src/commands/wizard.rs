@@ -0,0 +1,198 @@
+use anyhow::Result;
+use clap::{Command, ValueEnum};
+use dialoguer::{Confirm, Input, Select};
+use std::io::{self, IsTerminal};
+
+use crate::utils::field::Field;
+use crate::utils::project::{AuthStrategy, Database, IdStrategy, ProjectConfig};
+
+use super::generate_item::MiddlewareKind;
+use super::generate_relation::RelationKind;
+use super::generate_route::HttpMethod;
+use super::new::NewOptions;
+use super::presets::{list_presets, Preset, PresetFile};
+use super::schematics::user_templates;
+
+/// Schematics that ask for entity fields
+const SCHEMATICS_WITH_FIELDS: &[&str] = &["module", "entity", "dto"];
+
+/// Answers collected by `generate_wizard`
+pub struct GenerateAnswers {
+    pub schematic: String,
+    pub name: String,
+    pub fields: Vec<Field>,
+    /// Only asked for middlewares
+    pub middleware_kind: MiddlewareKind,
+    /// Only asked for routes
+    pub method: HttpMethod,
+    /// Only asked for config keys
+    pub key: Option<Field>,
+}
+
+/// Prompts only make sense when a person is on the other end of stdin/stdout.
+pub fn is_interactive() -> bool {
+    io::stdin().is_terminal() && io::stdout().is_terminal()
+}
+
+/// Ask which preset to start from.
+pub fn preset_wizard() -> Result<Preset> {
+    let presets = list_presets()?;
+    let labels: Vec<String> = presets.iter().map(|p| format!("{} - {}", p.name, p.description)).collect();
+    let selected = Select::new()
+        .with_prompt("Preset")
+        .items(labels.iter())
+        .default(0)
+        .interact()?;
+    Ok(presets[selected].clone())
+}
+
+/// Ask for everything `axumate new` needs; the preset and flags already given are used as the defaults.
+pub fn new_project_wizard(mut project: ProjectConfig, latest: bool, files: Vec<PresetFile>) -> Result<NewOptions> {
+    let name: String = Input::new().with_prompt("Project name").interact_text()?;
+
+    let databases = [Database::None, Database::Postgres, Database::Sqlite];
+    let selected = Select::new()
+        .with_prompt("Database")
+        .items(databases.iter())
+        .default(databases.iter().position(|d| *d == project.database).unwrap_or(0))
+        .interact()?;
+
+    project.database = databases[selected];
+
+    // only offer the id types the chosen database can store
+    let ids: Vec<IdStrategy> = IdStrategy::value_variants().iter().copied().filter(|id| id.check(project.database).is_ok()).collect();
+    let selected = Select::new()
+        .with_prompt("Id type")
+        .items(ids.iter())
+        .default(ids.iter().position(|id| *id == project.id_strategy()).unwrap_or(0))
+        .interact()?;
+    project.id = Some(ids[selected]);
+
+    project.openapi = Confirm::new()
+        .with_prompt("Serve OpenAPI docs at /docs?")
+        .default(project.openapi)
+        .interact()?;
+    project.tracing = Confirm::new()
+        .with_prompt("Set up tracing?")
+        .default(project.tracing)
+        .interact()?;
+    project.error_handling = Confirm::new()
+        .with_prompt("Generate a JSON error type (src/error.rs)?")
+        .default(project.error_handling)
+        .interact()?;
+    project.health = Confirm::new()
+        .with_prompt("Serve GET /health?")
+        .default(project.health)
+        .interact()?;
+
+    let strategies = AuthStrategy::value_variants();
+    let selected = Select::new()
+        .with_prompt("Authentication")
+        .items(strategies.iter())
+        .default(strategies.iter().position(|s| *s == project.auth).unwrap_or(0))
+        .interact()?;
+    project.auth = strategies[selected];
+
+    Ok(NewOptions { name, project, latest, files })
+}
+
+/// Ask which schematic to generate, its name and, where relevant, its fields.
+pub fn generate_wizard(generate: &Command) -> Result<GenerateAnswers> {
+    let mut schematics: Vec<String> = generate
+        .get_subcommands()
+        .filter(|s| !s.is_hide_set())
+        .map(|s| s.get_name().to_string())
+        .collect();
+    schematics.extend(user_templates().into_iter().map(|t| t.name));
+
+    let selected = Select::new()
+        .with_prompt("What do you want to generate?")
+        .items(schematics.iter())
+        .default(0)
+        .interact()?;
+    let schematic = schematics[selected].clone();
+
+    // auth and rbac are single modules with fixed names
+    let name: String = if schematic == "auth" || schematic == "rbac" {
+        String::new()
+    } else if schematic == "route" {
+        Input::new()
+            .with_prompt("Route (module:action)")
+            .validate_with(|input: &String| -> Result<(), String> {
+                match input.split_once(':') {
+                    Some((module, action)) if !module.is_empty() && !action.is_empty() => Ok(()),
+                    _ => Err("expected `module:action`, e.g. `hero:publish`".to_string()),
+                }
+            })
+            .interact_text()?
+    } else if schematic == "relation" {
+        Input::new()
+            .with_prompt("Relation (module has-many|belongs-to module)")
+            .validate_with(|input: &String| -> Result<(), String> {
+                match input.split_whitespace().collect::<Vec<_>>().as_slice() {
+                    [_, kind, _] if RelationKind::from_str(kind, true).is_ok() => Ok(()),
+                    _ => Err("expected e.g. `hero has-many power`".to_string()),
+                }
+            })
+            .interact_text()?
+    } else if schematic == "config-key" {
+        Input::new()
+            .with_prompt("Setting (name:Type)")
+            .validate_with(|input: &String| -> Result<(), String> { input.parse::<Field>().map(|_| ()) })
+            .interact_text()?
+    } else {
+        Input::new().with_prompt("Name").interact_text()?
+    };
+
+    let fields = if SCHEMATICS_WITH_FIELDS.contains(&schematic.as_str()) {
+        prompt_fields()?
+    } else {
+        Vec::new()
+    };
+
+    let middleware_kind = if schematic == "middleware" { prompt_middleware_kind()? } else { MiddlewareKind::default() };
+    let method = if schematic == "route" {
+        let methods = HttpMethod::value_variants();
+        let labels: Vec<&str> = methods.iter().map(|m| m.router()).collect();
+        methods[Select::new().with_prompt("Method").items(labels.iter()).default(0).interact()?]
+    } else {
+        HttpMethod::default()
+    };
+
+    let key = if schematic == "config-key" { Some(name.parse().map_err(anyhow::Error::msg)?) } else { None };
+
+    Ok(GenerateAnswers { schematic, name, fields, middleware_kind, method, key })
+}
+
+/// Pick one of the built-in middleware kinds, showing each one's description.
+fn prompt_middleware_kind() -> Result<MiddlewareKind> {
+    let kinds = MiddlewareKind::value_variants();
+    let labels: Vec<String> = kinds
+        .iter()
+        .filter_map(|kind| kind.to_possible_value())
+        .map(|value| match value.get_help() {
+            Some(help) => format!("{} — {}", value.get_name(), help),
+            None => value.get_name().to_string(),
+        })
+        .collect();
+    let selected = Select::new().with_prompt("Kind").items(labels.iter()).default(0).interact()?;
+    Ok(kinds[selected])
+}
+
+/// Ask for `name:Type` fields one at a time until an empty answer.
+pub fn prompt_fields() -> Result<Vec<Field>> {
+    let mut fields = Vec::new();
+    loop {
+        let answer: String = Input::new()
+            .with_prompt("Field (name:Type, empty to finish)")
+            .allow_empty(true)
+            .validate_with(|input: &String| -> Result<(), String> {
+                if input.trim().is_empty() { Ok(()) } else { input.parse::<Field>().map(|_| ()) }
+            })
+            .interact_text()?;
+        if answer.trim().is_empty() {
+            return Ok(fields);
+        }
+        fields.push(answer.parse().map_err(anyhow::Error::msg)?);
+    }
+}
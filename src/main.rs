@@ -1,10 +1,11 @@
 mod commands;
 mod utils;
 use anyhow::Result;
-use clap::{Parser, Subcommand};
-use std::path::Path;
-use std::process::Command;
+use clap::{CommandFactory, Parser, Subcommand, ValueEnum};
+use clap_complete::{ArgValueCandidates, CompleteEnv, Shell};
 use std::str;
+use utils::field::Field;
+use utils::project::{AuthStrategy, Database, IdStrategy, ProjectConfig};
 
 #[derive(Parser)]
 #[command(name = "axumate", version, about = "CLI for Axum project scaffolding")]
@@ -15,46 +16,437 @@ struct Cli {
 
 #[derive(Subcommand)]
 enum Commands {
-    /// Create a new Axum project
+    /// Create a new Axum project (interactive when run without a name in a terminal)
+    #[command(visible_alias = "n")]
     New {
-        name: String
+        name: Option<String>,
+        /// Start from a preset (minimal, rest-api, fullstack, microservice or one from ~/.axumate/presets/); flags below add to it
+        #[arg(long)]
+        preset: Option<String>,
+        /// Database backend
+        #[arg(long, value_enum)]
+        database: Option<Database>,
+        /// Serve OpenAPI docs at /docs
+        #[arg(long)]
+        openapi: bool,
+        /// Set up tracing: structured logs and a request TraceLayer (on by default without a preset)
+        #[arg(long)]
+        tracing: bool,
+        /// Leave tracing out; prints go to stdout instead
+        #[arg(long, conflicts_with = "tracing")]
+        no_tracing: bool,
+        /// Generate src/error.rs with a JSON `AppError`
+        #[arg(long)]
+        error_handling: bool,
+        /// Serve GET /health
+        #[arg(long)]
+        health: bool,
+        /// Generate an auth module with this strategy
+        #[arg(long, value_enum)]
+        auth: Option<AuthStrategy>,
+        /// Id type of generated modules (u32 in memory and i64 with a database by default)
+        #[arg(long, value_enum)]
+        id: Option<IdStrategy>,
+        /// Add the newest dependency versions with `cargo add` (needs network) instead of the pinned, tested set
+        #[arg(long)]
+        latest: bool,
     },
-    /// Generate boilerplate (controller, service, etc.)
+    /// Generate boilerplate (controller, service, etc.), interactive when run without a schematic in a terminal
+    #[command(visible_alias = "g", args_conflicts_with_subcommands = true)]
     Generate {
+        /// List every available schematic with its alias
+        #[arg(long)]
+        list: bool,
         #[command(subcommand)]
-        kind: GenerateKind,
-    }
+        kind: Option<GenerateKind>,
+    },
+    /// Manage API versions of modules
+    #[command(visible_alias = "v")]
+    Version {
+        #[command(subcommand)]
+        action: VersionAction,
+    },
+    /// Print the shell completion script (e.g. `source <(axumate completions bash)`)
+    Completions {
+        shell: Shell,
+    },
+    /// Print the man page
+    Man,
+}
+
+#[derive(Subcommand)]
+enum VersionAction {
+    /// Copy a module's controller and DTOs to a new API version, sharing its service and entities
+    Bump {
+        #[arg(add = ArgValueCandidates::new(commands::completions::module_name_candidates))]
+        module: String,
+        /// The new version, e.g. `v2`
+        #[arg(value_parser = commands::version::parse_version)]
+        version: String,
+        /// Version to copy from (defaults to the unversioned module in src/<module>)
+        #[arg(long, value_parser = commands::version::parse_version)]
+        from: Option<String>,
+    },
 }
 
 #[derive(Subcommand)]
 enum GenerateKind {
     /// Generate a controller
-    Controller { name: String },
+    #[command(visible_alias = "co")]
+    Controller {
+        #[arg(add = ArgValueCandidates::new(commands::completions::module_name_candidates))]
+        name: String,
+        /// Id type of the handlers (defaults to the project's `id`)
+        #[arg(long, value_enum)]
+        id: Option<IdStrategy>,
+    },
     /// Generate a service
-    Service { name: String },
+    #[command(visible_alias = "s")]
+    Service {
+        #[arg(add = ArgValueCandidates::new(commands::completions::module_name_candidates))]
+        name: String,
+        /// Id type of the handlers (defaults to the project's `id`)
+        #[arg(long, value_enum)]
+        id: Option<IdStrategy>,
+    },
     /// Generate an entity
-    Entity { name: String },
+    #[command(visible_alias = "e")]
+    Entity {
+        #[arg(add = ArgValueCandidates::new(commands::completions::module_name_candidates))]
+        name: String,
+        /// Entity field as `name:Type`, repeatable
+        #[arg(long = "field", value_name = "NAME:TYPE")]
+        fields: Vec<Field>,
+        /// Id type of the entity (defaults to the project's `id`)
+        #[arg(long, value_enum)]
+        id: Option<IdStrategy>,
+        /// Add `created_at` and `updated_at` fields
+        #[arg(long)]
+        timestamps: bool,
+        /// Add a `deleted_at` field
+        #[arg(long)]
+        soft_delete: bool,
+    },
     /// Generate a dto
-    DTO { name: String },
-    /// Generate a module
-    Module { name: String },
+    #[command(visible_alias = "d")]
+    Dto {
+        #[arg(add = ArgValueCandidates::new(commands::completions::module_name_candidates))]
+        name: String,
+        /// DTO field as `name:Type`, repeatable
+        #[arg(long = "field", value_name = "NAME:TYPE")]
+        fields: Vec<Field>,
+    },
     /// Generate a module
-    Middleware { name: String },
+    #[command(visible_alias = "mo")]
+    Module {
+        name: String,
+        /// Entity field as `name:Type`, repeatable (defaults to two String placeholders)
+        #[arg(long = "field", value_name = "NAME:TYPE")]
+        fields: Vec<Field>,
+        /// Require `<name>:create`, `:read`, `:update` and `:delete` permissions on the CRUD handlers (needs the auth module)
+        #[arg(long)]
+        permissions: bool,
+        /// Place the module in an API version namespace, e.g. `v2`: src/v2/<name>, served under `/v2/<name>`
+        #[arg(long, value_parser = commands::version::parse_version)]
+        version: Option<String>,
+        /// Id type of the module (defaults to the project's `id`, else u32 in memory and i64 with a database)
+        #[arg(long, value_enum)]
+        id: Option<IdStrategy>,
+        /// Add `created_at`/`updated_at`, set by the service on create and update
+        #[arg(long)]
+        timestamps: bool,
+        /// Add `deleted_at`: `remove` marks rows deleted and lookups skip them
+        #[arg(long)]
+        soft_delete: bool,
+    },
+    /// Add an endpoint to a module: a controller handler, a service stub and a route
+    #[command(visible_alias = "ro")]
+    Route {
+        /// Module to add it to
+        #[arg(add = ArgValueCandidates::new(commands::completions::module_name_candidates))]
+        module: String,
+        /// Name of the handler and service function, e.g. `publish`
+        action: String,
+        #[arg(long, value_enum, default_value_t)]
+        method: commands::generate_route::HttpMethod,
+        /// Path under the module's prefix, e.g. `/{id}/publish` (defaults to `/<action>`)
+        #[arg(long)]
+        path: Option<String>,
+        /// Take this DTO as JSON body, added to the module's DTOs if missing
+        #[arg(long, value_name = "TYPE")]
+        body: Option<String>,
+        /// Take this struct as query string, added to the module's DTOs if missing
+        #[arg(long, value_name = "TYPE")]
+        query: Option<String>,
+    },
+    /// Relate two modules one-to-many, e.g. `hero has-many power`: a foreign key and nested routes
+    #[command(visible_alias = "rel")]
+    Relation {
+        #[arg(add = ArgValueCandidates::new(commands::completions::module_name_candidates))]
+        from: String,
+        #[arg(value_enum)]
+        kind: commands::generate_relation::RelationKind,
+        #[arg(add = ArgValueCandidates::new(commands::completions::module_name_candidates))]
+        to: String,
+    },
+    /// Generate a middleware
+    #[command(visible_alias = "mi")]
+    Middleware {
+        name: String,
+        /// What the middleware does
+        #[arg(long, value_enum, default_value_t)]
+        kind: commands::generate_item::MiddlewareKind,
+        /// Attach it to this module's routes() instead of the whole app
+        #[arg(long, conflicts_with = "route", add = ArgValueCandidates::new(commands::completions::module_name_candidates))]
+        module: Option<String>,
+        /// Attach it to a single route, e.g. `/hero/{id}`
+        #[arg(long)]
+        route: Option<String>,
+        /// Run it before this other middleware
+        #[arg(long, value_name = "OTHER", conflicts_with = "after")]
+        before: Option<String>,
+        /// Run it after this other middleware
+        #[arg(long, value_name = "OTHER")]
+        after: Option<String>,
+        /// Take `State<AppState>` (config, database pool) and attach it app-wide with `from_fn_with_state`
+        #[arg(long, conflicts_with_all = ["module", "route"])]
+        stateful: bool,
+    },
+    /// Generate a tower Layer + Service pair in src/layers/
+    #[command(visible_alias = "l")]
+    Layer {
+        name: String,
+        /// Attach it to this module's routes() instead of the whole app
+        #[arg(long, conflicts_with = "route", add = ArgValueCandidates::new(commands::completions::module_name_candidates))]
+        module: Option<String>,
+        /// Attach it to a single route, e.g. `/hero/{id}`
+        #[arg(long)]
+        route: Option<String>,
+        /// Run it before this middleware or layer
+        #[arg(long, value_name = "OTHER", conflicts_with = "after")]
+        before: Option<String>,
+        /// Run it after this middleware or layer
+        #[arg(long, value_name = "OTHER")]
+        after: Option<String>,
+    },
+    /// Generate a guard: an extractor rejecting unauthorised requests with 401/403
+    #[command(visible_alias = "gu")]
+    Guard {
+        name: String,
+        /// Add the guard to handlers of a module's controller, e.g. `hero:update,remove` (repeatable)
+        #[arg(long, value_name = "MODULE:HANDLERS")]
+        apply: Vec<commands::generate_guard::GuardTarget>,
+    },
+    /// Generate the auth module: register/login/refresh endpoints, a `CurrentUser` extractor and an `AuthGuard`
+    #[command(visible_alias = "au")]
+    Auth {
+        /// How users authenticate
+        #[arg(long, value_enum, default_value = "jwt")]
+        strategy: AuthStrategy,
+    },
+    /// Generate role-based access control: roles, permissions and a `RequirePermission` extractor
+    #[command(visible_alias = "rb")]
+    Rbac,
+    /// Add a setting to the generated AppConfig
+    #[command(visible_alias = "ck")]
+    ConfigKey {
+        /// Setting as `name:Type`, e.g. `jwt_secret:String`
+        #[arg(value_name = "NAME:TYPE")]
+        key: Field,
+        /// Default value as a TOML literal, e.g. `8080`, `true` or '"secret"'
+        #[arg(long)]
+        default: Option<String>,
+    },
+    /// A user-defined schematic from .axumate/templates/
+    #[command(external_subcommand)]
+    Custom(Vec<String>),
+}
+
+impl GenerateKind {
+    /// Turn wizard answers into the subcommand the flags would have produced.
+    fn from_answers(answers: commands::wizard::GenerateAnswers) -> Result<Self> {
+        let commands::wizard::GenerateAnswers { schematic, name, fields, middleware_kind, method, key } = answers;
+        let kind = match schematic.as_str() {
+            "controller" => GenerateKind::Controller { name, id: None },
+            "service" => GenerateKind::Service { name, id: None },
+            "entity" => GenerateKind::Entity { name, fields, id: None, timestamps: false, soft_delete: false },
+            "dto" => GenerateKind::Dto { name, fields },
+            "module" => GenerateKind::Module {
+                name,
+                fields,
+                permissions: false,
+                version: None,
+                id: None,
+                timestamps: false,
+                soft_delete: false,
+            },
+            "auth" => GenerateKind::Auth { strategy: AuthStrategy::Jwt },
+            "rbac" => GenerateKind::Rbac,
+            "route" => {
+                let (module, action) = name.split_once(':').unwrap_or((name.as_str(), ""));
+                GenerateKind::Route {
+                    module: module.to_string(),
+                    action: action.to_string(),
+                    method,
+                    path: None,
+                    body: None,
+                    query: None,
+                }
+            }
+            "relation" => match name.split_whitespace().collect::<Vec<_>>().as_slice() {
+                [from, kind, to] => {
+                    use commands::generate_relation::RelationKind;
+                    GenerateKind::Relation {
+                        from: from.to_string(),
+                        kind: RelationKind::from_str(kind, true).map_err(anyhow::Error::msg)?,
+                        to: to.to_string(),
+                    }
+                }
+                _ => anyhow::bail!("expected `module has-many|belongs-to module`, got `{}`", name),
+            },
+            "guard" => GenerateKind::Guard { name, apply: Vec::new() },
+            "layer" => GenerateKind::Layer { name, module: None, route: None, before: None, after: None },
+            "middleware" => GenerateKind::Middleware {
+                name,
+                kind: middleware_kind,
+                module: None,
+                route: None,
+                before: None,
+                after: None,
+                stateful: false,
+            },
+            "config-key" => match key {
+                Some(key) => GenerateKind::ConfigKey { key, default: None },
+                None => GenerateKind::Custom(vec![schematic, name]),
+            },
+            _ => GenerateKind::Custom(vec![schematic, name]),
+        };
+        Ok(kind)
+    }
+}
+
+/// Scope and order of a middleware or layer from its `--module`/`--route`/`--before`/`--after` flags
+fn placement(
+    module: Option<String>,
+    route: Option<String>,
+    before: Option<String>,
+    after: Option<String>,
+) -> (commands::generate_item::MiddlewareScope, commands::generate_item::LayerOrder) {
+    use commands::generate_item::{LayerOrder, MiddlewareScope};
+    let scope = match (module, route) {
+        (Some(module), _) => MiddlewareScope::Module(module),
+        (None, Some(route)) => MiddlewareScope::Route(route),
+        (None, None) => MiddlewareScope::App,
+    };
+    let order = match (before, after) {
+        (Some(other), _) => LayerOrder::Before(other),
+        (None, Some(other)) => LayerOrder::After(other),
+        (None, None) => LayerOrder::Outermost,
+    };
+    (scope, order)
+}
+
+fn generate(kind: GenerateKind) -> Result<()> {
+    match kind {
+        GenerateKind::Module { name, fields, permissions, version, id, timestamps, soft_delete } => {
+            let options =
+                commands::generate_module::ModuleOptions { name, fields, permissions, version, id, timestamps, soft_delete };
+            commands::generate_module::generate_module(options)?
+        }
+        GenerateKind::Controller { name, id } => commands::generate_item::generate_controller(name, id)?,
+        GenerateKind::Service { name, id } => commands::generate_item::generate_service(name, id)?,
+        GenerateKind::Entity { name, fields, id, timestamps, soft_delete } => {
+            commands::generate_item::generate_entity(name, fields, id, timestamps, soft_delete)?
+        }
+        GenerateKind::Dto { name, fields } => commands::generate_item::generate_dto(name, fields)?,
+        GenerateKind::Route { module, action, method, path, body, query } => {
+            let options = commands::generate_route::RouteOptions { module, action, method, path, body, query };
+            commands::generate_route::generate_route(options)?
+        }
+        GenerateKind::Relation { from, kind, to } => commands::generate_relation::generate_relation(from, kind, to)?,
+        GenerateKind::Middleware { name, kind, module, route, before, after, stateful } => {
+            let (scope, order) = placement(module, route, before, after);
+            let options = commands::generate_item::MiddlewareOptions { name, kind, scope, order, stateful };
+            commands::generate_item::generate_middleware(options)?
+        }
+        GenerateKind::Layer { name, module, route, before, after } => {
+            let (scope, order) = placement(module, route, before, after);
+            commands::generate_item::generate_layer(name, scope, order)?
+        }
+        GenerateKind::Guard { name, apply } => commands::generate_guard::generate_guard(name, apply)?,
+        GenerateKind::Auth { strategy } => commands::generate_auth::generate_auth(strategy)?,
+        GenerateKind::Rbac => commands::generate_rbac::generate_rbac()?,
+        GenerateKind::ConfigKey { key, default } => commands::generate_config::generate_config_key(key, default)?,
+        GenerateKind::Custom(args) => match args.as_slice() {
+            [schematic, name] => commands::schematics::generate_from_user_template(schematic, name)?,
+            _ => anyhow::bail!("Usage: axumate generate <schematic> <name>"),
+        },
+    }
+    Ok(())
 }
 
 fn main() -> Result<()> {
+    // answer `COMPLETE=<shell> axumate ...` requests from the completion scripts before anything else
+    CompleteEnv::with_factory(Cli::command).complete();
+
     let cli = Cli::parse();
 
     match cli.command {
-        Commands::New { name } => commands::new::create_new_project(name)?,
-        Commands::Generate { kind } => match kind {
-            GenerateKind::Module { name } => commands::generate_module::generate_module(name)?,
-            GenerateKind::Controller { name } => commands::generate_item::generate_controller(name)?,
-            GenerateKind::Service { name } => commands::generate_item::generate_service(name)?,
-            GenerateKind::Entity { name } => commands::generate_item::generate_entity(name)?,
-            GenerateKind::DTO { name } => commands::generate_item::generate_dto(name)?,
-            GenerateKind::Middleware { name } => commands::generate_item::generate_middleware(name)?,
-        },
+        Commands::New { name, preset, database, openapi, tracing, no_tracing, error_handling, health, auth, id, latest } => {
+            let interactive = name.is_none() && commands::wizard::is_interactive();
+            if name.is_none() && !interactive {
+                anyhow::bail!("Missing project name. Usage: axumate new <name>");
+            }
+
+            let preset = match preset {
+                Some(preset) => Some(commands::presets::load_preset(&preset)?),
+                None if interactive => Some(commands::wizard::preset_wizard()?),
+                None => None,
+            };
+            let (mut project, files) = match preset {
+                Some(preset) => (preset.project, preset.files),
+                None => (ProjectConfig { tracing: true, ..ProjectConfig::default() }, Vec::new()),
+            };
+            if let Some(database) = database {
+                project.database = database;
+            }
+            project.openapi |= openapi;
+            project.tracing = (project.tracing || tracing) && !no_tracing;
+            project.error_handling |= error_handling;
+            project.health |= health;
+            if let Some(auth) = auth {
+                project.auth = auth;
+            }
+            if id.is_some() {
+                project.id = id;
+            }
+
+            let options = match name {
+                Some(name) => commands::new::NewOptions { name, project, latest, files },
+                None => commands::wizard::new_project_wizard(project, latest, files)?,
+            };
+            commands::new::create_new_project(options)?
+        }
+        Commands::Generate { list: true, .. } => {
+            let cli = Cli::command();
+            let generate = cli.find_subcommand("generate").expect("generate subcommand");
+            commands::schematics::print_schematics(generate);
+        }
+        Commands::Generate { kind: None, .. } => {
+            if !commands::wizard::is_interactive() {
+                anyhow::bail!("Missing schematic. Run `axumate generate --list` to see what is available")
+            }
+            let cli = Cli::command();
+            let generate_cmd = cli.find_subcommand("generate").expect("generate subcommand");
+            let answers = commands::wizard::generate_wizard(generate_cmd)?;
+            generate(GenerateKind::from_answers(answers)?)?
+        }
+        Commands::Generate { kind: Some(kind), .. } => generate(kind)?,
+        Commands::Version { action: VersionAction::Bump { module, version, from } } => {
+            commands::version::bump_version(module, version, from)?
+        }
+        Commands::Completions { shell } => commands::completions::print_completions(shell)?,
+        Commands::Man => commands::completions::print_man_page(Cli::command())?,
     }
 
     Ok(())
@@ -0,0 +1,136 @@
+use anyhow::{Context, Result};
+use std::collections::HashSet;
+use std::ops::Range;
+use std::path::Path;
+use syn::spanned::Spanned;
+use syn::{Expr, ExprMethodCall, Fields, File, Item, ItemFn, UseTree};
+
+/// Parse the Rust source of `path`, naming the file in syntax errors.
+pub fn parse_file(path: &Path, content: &str) -> Result<File> {
+    syn::parse_file(content).with_context(|| format!("Could not parse {}", path.to_string_lossy()))
+}
+
+/// Byte offsets of `node` in the source it was parsed from.
+pub fn byte_range(node: &impl Spanned) -> Range<usize> {
+    node.span().byte_range()
+}
+
+/// The free function `name` at the top level of `file`.
+pub fn find_fn<'a>(file: &'a File, name: &str) -> Option<&'a ItemFn> {
+    file.items.iter().find_map(|item| match item {
+        Item::Fn(f) if f.sig.ident == name => Some(f),
+        _ => None,
+    })
+}
+
+/// Whether `file` declares a struct named `name` at the top level.
+pub fn has_struct(file: &File, name: &str) -> bool {
+    file.items.iter().any(|item| matches!(item, Item::Struct(s) if s.ident == name))
+}
+
+/// Whether the struct `name` derives `derive`, written bare or as a path such as `utoipa::ToSchema`.
+pub fn derives(file: &File, name: &str, derive: &str) -> bool {
+    file.items.iter().any(|item| match item {
+        Item::Struct(s) if s.ident == name => s.attrs.iter().filter(|a| a.path().is_ident("derive")).any(|a| {
+            let mut found = false;
+            let _ = a.parse_nested_meta(|meta| {
+                found |= meta.path.segments.last().is_some_and(|s| s.ident == derive);
+                Ok(())
+            });
+            found
+        }),
+        _ => false,
+    })
+}
+
+/// Named fields of the struct `name` with their types as written in `content`, the source of `file`.
+pub fn struct_fields(file: &File, content: &str, name: &str) -> Option<Vec<(String, String)>> {
+    file.items.iter().find_map(|item| match item {
+        Item::Struct(s) if s.ident == name => Some(
+            s.fields
+                .iter()
+                .filter_map(|field| Some((field.ident.as_ref()?.to_string(), content[byte_range(&field.ty)].to_string())))
+                .collect(),
+        ),
+        _ => None,
+    })
+}
+
+/// Append `field` (e.g. `pub hero_id: u32`) to the struct `name` in `content`.
+/// Returns false when `content` has no such struct with named fields.
+pub fn add_struct_field(content: &mut String, name: &str, field: &str) -> bool {
+    let Ok(file) = syn::parse_file(content) else {
+        return false;
+    };
+    let fields = file.items.iter().find_map(|item| match item {
+        Item::Struct(s) if s.ident == name => match &s.fields {
+            Fields::Named(fields) => Some(fields),
+            _ => None,
+        },
+        _ => None,
+    });
+    let Some(fields) = fields else {
+        return false;
+    };
+    let close = fields.brace_token.span.close().byte_range().start;
+    content.insert_str(close, &format!("    {},\n", field));
+    if let Some(last) = fields.named.last().filter(|_| !fields.named.trailing_punct()) {
+        content.insert(byte_range(last).end, ',');
+    }
+    true
+}
+
+/// Names brought into scope by the `use` items of `file`, after renames and through groups.
+pub fn imported_names(file: &File) -> HashSet<String> {
+    fn collect(tree: &UseTree, names: &mut HashSet<String>) {
+        match tree {
+            UseTree::Path(path) => collect(&path.tree, names),
+            UseTree::Name(name) => {
+                names.insert(name.ident.to_string());
+            }
+            UseTree::Rename(rename) => {
+                names.insert(rename.rename.to_string());
+            }
+            UseTree::Group(group) => group.items.iter().for_each(|tree| collect(tree, names)),
+            UseTree::Glob(_) => {}
+        }
+    }
+    let mut names = HashSet::new();
+    for item in &file.items {
+        if let Item::Use(item) = item {
+            collect(&item.tree, &mut names);
+        }
+    }
+    names
+}
+
+/// Insert the `use` lines of the `(name, line)` pairs whose name `file` does not import yet, after its last `use` item.
+/// `file` must be parsed from `content`.
+pub fn add_imports(content: &mut String, file: &File, imports: &[(&str, String)]) {
+    let imported = imported_names(file);
+    let missing: String = imports
+        .iter()
+        .filter(|(name, _)| !imported.contains(*name))
+        .map(|(_, line)| format!("\n{}", line))
+        .collect();
+    if missing.is_empty() {
+        return;
+    }
+    let last_use = file.items.iter().rfind(|item| matches!(item, Item::Use(_)));
+    match last_use {
+        Some(item) => content.insert_str(byte_range(item).end, &missing),
+        None => content.insert_str(0, &format!("{}\n", missing.trim_start())),
+    }
+}
+
+/// The method calls of a builder chain such as `Router::new().route(..).layer(..)`, outermost (last) first,
+/// and the expression the chain starts from (`Router::new()`).
+pub fn method_chain(expr: &Expr) -> (Vec<&ExprMethodCall>, &Expr) {
+    let mut calls = Vec::new();
+    let mut current = expr;
+    while let Expr::MethodCall(call) = current {
+        calls.push(call);
+        current = &call.receiver;
+    }
+    (calls, current)
+}
@@ -0,0 +1,58 @@
+use anyhow::{bail, Result};
+use std::str::FromStr;
+
+use super::project::Database;
+
+/// A `name:Type` field of a generated entity and its DTOs
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Field {
+    pub name: String,
+    pub ty: String,
+}
+
+impl Field {
+    pub fn new(name: &str, ty: &str) -> Self {
+        Field { name: name.to_string(), ty: ty.to_string() }
+    }
+
+    /// The placeholder fields used when none are given.
+    pub fn defaults() -> Vec<Field> {
+        vec![Field::new("field1", "String"), Field::new("field2", "String")]
+    }
+
+    /// SQL column type for this field, or an error for Rust types sqlx cannot map on `db`.
+    pub fn sql_type(&self, db: Database) -> Result<&'static str> {
+        let sql = match (self.ty.as_str(), db) {
+            ("String", _) => "TEXT",
+            ("bool", Database::Postgres) => "BOOLEAN",
+            ("bool", _) => "INTEGER",
+            ("i16", Database::Postgres) => "SMALLINT",
+            ("i32", Database::Postgres) => "INTEGER",
+            ("i64", Database::Postgres) => "BIGINT",
+            ("i16" | "i32" | "i64" | "u8" | "u16" | "u32", Database::Sqlite) => "INTEGER",
+            ("f32", Database::Postgres) => "REAL",
+            ("f64", Database::Postgres) => "DOUBLE PRECISION",
+            ("f32" | "f64", Database::Sqlite) => "REAL",
+            (ty, db) => bail!(
+                "Field `{}` has type `{}`, which is not supported with the {} database",
+                self.name,
+                ty,
+                db
+            ),
+        };
+        Ok(sql)
+    }
+}
+
+impl FromStr for Field {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once(':') {
+            Some((name, ty)) if !name.trim().is_empty() && !ty.trim().is_empty() => {
+                Ok(Field::new(name.trim(), ty.trim()))
+            }
+            _ => Err(format!("expected `name:Type`, got `{}`", s)),
+        }
+    }
+}
@@ -91,3 +91,16 @@ pub fn current_mod_file() -> PathBuf {
         cwd.join("mod.rs")
     }
 }
+
+/// List the modules under src/ (directories that contain a mod.rs), sorted by name.
+pub fn list_modules() -> Vec<String> {
+    let mut modules: Vec<String> = fs::read_dir("src")
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|entry| entry.path().join("mod.rs").exists())
+        .filter_map(|entry| entry.file_name().to_str().map(str::to_string))
+        .collect();
+    modules.sort();
+    modules
+}
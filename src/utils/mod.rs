@@ -1,2 +1,9 @@
+pub mod ast;
 pub mod common;
-pub mod file;
\ No newline at end of file
+pub mod field;
+pub mod file;
+pub mod project;
+// main.rs compiles this tree too, where the helpers go unused
+#[cfg(any(test, feature = "testing"))]
+#[allow(dead_code)]
+pub mod testing;
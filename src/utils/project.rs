@@ -0,0 +1,183 @@
+use anyhow::{bail, Context, Result};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+/// File at the project root recording the choices made by `axumate new`, read back by the generators.
+pub const PROJECT_FILE: &str = "axumate.toml";
+
+/// Database backend of a generated project
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Database {
+    /// In-memory storage only
+    #[default]
+    None,
+    /// PostgreSQL through sqlx
+    Postgres,
+    /// SQLite through sqlx
+    Sqlite,
+}
+
+impl Database {
+    pub fn is_sql(self) -> bool {
+        self != Database::None
+    }
+}
+
+impl fmt::Display for Database {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Database::None => "none",
+            Database::Postgres => "postgres",
+            Database::Sqlite => "sqlite",
+        };
+        f.write_str(name)
+    }
+}
+
+/// How a generated project authenticates users
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum AuthStrategy {
+    /// No auth module
+    #[default]
+    None,
+    /// Access and refresh JSON Web Tokens
+    Jwt,
+    /// Signed-cookie sessions with CSRF protection
+    Session,
+}
+
+impl fmt::Display for AuthStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            AuthStrategy::None => "none",
+            AuthStrategy::Jwt => "jwt",
+            AuthStrategy::Session => "session",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Type of the ids of generated modules, which also decides how new ids are made
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum IdStrategy {
+    /// `u32` from a counter, or an autoincrement column (SQLite)
+    U32,
+    /// `i64` from a counter, or an autoincrement column
+    I64,
+    /// Time-ordered (v7) `uuid::Uuid`
+    Uuid,
+    /// `ulid::Ulid` (in-memory storage only)
+    Ulid,
+}
+
+impl IdStrategy {
+    /// Rust type of the ids, as written in the generated code
+    pub fn rust_type(self) -> &'static str {
+        match self {
+            IdStrategy::U32 => "u32",
+            IdStrategy::I64 => "i64",
+            IdStrategy::Uuid => "uuid::Uuid",
+            IdStrategy::Ulid => "ulid::Ulid",
+        }
+    }
+
+    /// The strategy of an `id` field written with type `ty`
+    pub fn from_type(ty: &str) -> Option<Self> {
+        match ty.rsplit("::").next()? {
+            "u32" => Some(IdStrategy::U32),
+            "i64" => Some(IdStrategy::I64),
+            "Uuid" => Some(IdStrategy::Uuid),
+            "Ulid" => Some(IdStrategy::Ulid),
+            _ => None,
+        }
+    }
+
+    /// Whether ids are made by the service (UUIDs, ULIDs) rather than counted
+    pub fn is_random(self) -> bool {
+        matches!(self, IdStrategy::Uuid | IdStrategy::Ulid)
+    }
+
+    /// SQL type of id columns, both primary and foreign keys
+    pub fn sql_type(self, database: Database) -> &'static str {
+        match (self, database) {
+            (IdStrategy::Uuid | IdStrategy::Ulid, Database::Postgres) => "UUID",
+            (IdStrategy::Uuid | IdStrategy::Ulid, _) => "BLOB",
+            (_, Database::Postgres) => "BIGINT",
+            _ => "INTEGER",
+        }
+    }
+
+    /// Reject the strategies sqlx cannot store in `database`.
+    pub fn check(self, database: Database) -> Result<()> {
+        match (self, database) {
+            (IdStrategy::U32, Database::Postgres) => bail!("Postgres has no unsigned integers, use `--id i64`"),
+            (IdStrategy::Ulid, Database::Postgres | Database::Sqlite) => {
+                bail!("sqlx cannot store ULIDs, use `--id uuid` for time-ordered ids with a database")
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+impl fmt::Display for IdStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            IdStrategy::U32 => "u32",
+            IdStrategy::I64 => "i64",
+            IdStrategy::Uuid => "uuid",
+            IdStrategy::Ulid => "ulid",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Project-wide settings stored in axumate.toml
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProjectConfig {
+    pub database: Database,
+    pub openapi: bool,
+    pub tracing: bool,
+    /// Generate src/error.rs with an `AppError` that renders as JSON
+    pub error_handling: bool,
+    /// Serve `GET /health`
+    pub health: bool,
+    /// Auth module generated by `axumate generate auth`
+    pub auth: AuthStrategy,
+    /// Id type of generated modules; `u32` in memory and `i64` with a database when unset
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<IdStrategy>,
+}
+
+impl ProjectConfig {
+    /// The id strategy of new modules
+    pub fn id_strategy(&self) -> IdStrategy {
+        match self.id {
+            Some(id) => id,
+            None if self.database.is_sql() => IdStrategy::I64,
+            None => IdStrategy::U32,
+        }
+    }
+
+    /// Load axumate.toml from the current directory, falling back to defaults when it is missing.
+    pub fn load() -> Result<Self> {
+        let path = Path::new(PROJECT_FILE);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(path)?;
+        toml::from_str(&content).with_context(|| format!("Invalid {}", PROJECT_FILE))
+    }
+
+    /// Write axumate.toml into `dir`.
+    pub fn save(&self, dir: &Path) -> Result<()> {
+        fs::write(dir.join(PROJECT_FILE), toml::to_string(self)?)?;
+        Ok(())
+    }
+}
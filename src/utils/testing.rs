@@ -0,0 +1,28 @@
+use std::env;
+use std::fs;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::Path;
+use std::sync::Mutex;
+
+/// Serialises tests that change the process working directory.
+static CWD_LOCK: Mutex<()> = Mutex::new(());
+
+/// Run `f` inside a scratch project (a temp dir with an empty `src/`), restoring the cwd afterwards.
+pub fn in_temp_project<T>(name: &str, f: impl FnOnce(&Path) -> T) -> T {
+    let _guard = CWD_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+
+    let root = env::temp_dir().join(format!("axumate-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&root);
+    fs::create_dir_all(root.join("src")).unwrap();
+
+    let previous = env::current_dir().unwrap();
+    env::set_current_dir(&root).unwrap();
+    let result = panic::catch_unwind(AssertUnwindSafe(|| f(&root)));
+    env::set_current_dir(previous).unwrap();
+    let _ = fs::remove_dir_all(&root);
+
+    match result {
+        Ok(value) => value,
+        Err(err) => panic::resume_unwind(err),
+    }
+}
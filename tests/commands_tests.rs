@@ -1,60 +1,166 @@
-use std::fs;
 use std::env;
-use axumate::commands::{ generate_module::{generate_controller, generate_service, generate_dto, generate_entity}};
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::Duration;
+use axumate::commands::generate_config::generate_config_key;
+use axumate::commands::generate_item::{generate_middleware, LayerOrder, MiddlewareKind, MiddlewareOptions, MiddlewareScope};
+use axumate::commands::{ generate_module::{generate_controller, generate_service, generate_dto, generate_entity, generate_module, ModuleOptions}};
+use axumate::commands::new::{create_new_project, NewOptions};
+use axumate::commands::templates::dependencies::dependencies;
+use axumate::commands::templates::generate_module_templates::ModuleSpec;
+use axumate::commands::templates::new_templates::main_template;
+use axumate::utils::field::Field;
+use axumate::utils::project::{Database, ProjectConfig};
+use axumate::utils::testing::in_temp_project;
 
-/// create_new_project actually runs cargo new and cargo add, which will download dependencies — too heavy for a normal unit test.
 #[test]
-fn integration_generate_controller() {
-    let dir = env::temp_dir();
-    let file_path = dir.join("my_controller.rs");
-    let file_path_str = file_path.to_str().unwrap();
-
-    generate_controller("my".into()).unwrap();
+fn integration_new_project_is_offline_and_pinned() {
+    in_temp_project("new", |root| {
+        let project = ProjectConfig { database: Database::Sqlite, openapi: false, tracing: true, ..ProjectConfig::default() };
+        create_new_project(NewOptions { name: "app".into(), project: project.clone(), latest: false, files: vec![] }).unwrap();
+
+        let manifest: toml::Table = fs::read_to_string(root.join("app/Cargo.toml")).unwrap().parse().unwrap();
+        let deps = manifest["dependencies"].as_table().unwrap();
+        for dep in dependencies(&project) {
+            assert!(deps.contains_key(dep.name), "missing {}", dep.name);
+        }
+        assert_eq!(deps["axum"].as_str(), Some("0.8.9"));
+
+        assert!(root.join("app/src/db.rs").exists());
+        assert!(root.join("app/src/config.rs").exists());
+        assert!(root.join("app/src/shutdown.rs").exists());
+        let main_rs = fs::read_to_string(root.join("app/src/main.rs")).unwrap();
+        assert!(main_rs.contains("shutdown::serve(listener, app, drain_timeout)"));
+        assert!(main_rs.contains("db::pool().close().await;"));
+        assert!(main_rs.contains("telemetry::init(&config.log_format);"));
+        assert!(main_rs.contains("let app = app.layer(TraceLayer::new_for_http());"));
+        assert!(root.join("app/src/telemetry.rs").exists());
+        let default_toml = fs::read_to_string(root.join("app/config/default.toml")).unwrap();
+        assert!(default_toml.contains("database_url = \"sqlite://app.db?mode=rwc\""));
+        assert!(root.join("app/migrations").is_dir());
+        assert!(fs::read_to_string(root.join("app/axumate.toml")).unwrap().contains("database = \"sqlite\""));
+    });
+}
 
-    assert!(file_path.exists());
+#[test]
+fn integration_generate_controller() {
+    in_temp_project("controller", |root| {
+        generate_controller(&ModuleSpec::new("my", vec![]).unwrap()).unwrap();
 
-    // cleanup
-    let _ = fs::remove_file(file_path_str);
+        assert!(root.join("src/my/my_controller.rs").exists());
+        let lib_rs = fs::read_to_string(root.join("src/lib.rs")).unwrap();
+        assert!(lib_rs.contains("pub mod my;"));
+    });
 }
 
 #[test]
 fn integration_generate_service() {
-    let dir = env::temp_dir();
-    let file_path = dir.join("my_service.rs");
-    let file_path_str = file_path.to_str().unwrap();
-
-    generate_service("my".into()).unwrap();
-
-    assert!(file_path.exists());
+    in_temp_project("service", |root| {
+        generate_service(&ModuleSpec::new("my", vec![]).unwrap()).unwrap();
 
-    // cleanup
-    let _ = fs::remove_file(file_path_str);
+        assert!(root.join("src/my/my_service.rs").exists());
+    });
 }
 
 #[test]
 fn integration_generate_dto() {
-    let dir = env::temp_dir();
-    let file_path = dir.join("my_dto.rs");
-    let file_path_str = file_path.to_str().unwrap();
+    in_temp_project("dto", |root| {
+        generate_dto(&ModuleSpec::new("my", vec![]).unwrap()).unwrap();
 
-    generate_dto("my".into()).unwrap();
+        assert!(root.join("src/my/dto/my_dto.rs").exists());
+    });
+}
 
-    assert!(file_path.exists());
+#[test]
+fn integration_generate_entity() {
+    in_temp_project("entity", |root| {
+        generate_entity(&ModuleSpec::new("my", vec![]).unwrap()).unwrap();
 
-    // cleanup
-    let _ = fs::remove_file(file_path_str);
+        assert!(root.join("src/my/entities/my_entity.rs").exists());
+    });
 }
 
 #[test]
-fn integration_generate_entity() {
-    let dir = env::temp_dir();
-    let file_path = dir.join("my_entity.rs");
-    let file_path_str = file_path.to_str().unwrap();
+fn integration_generate_module_with_database_and_openapi() {
+    in_temp_project("module-db", |root| {
+        let project = ProjectConfig { database: Database::Postgres, openapi: true, tracing: false, ..ProjectConfig::default() };
+        project.save(root).unwrap();
+        fs::write(root.join("src/main.rs"), main_template("app", &project)).unwrap();
+
+        generate_module(ModuleOptions { name: "hero".into(), fields: vec![Field::new("name", "String")], permissions: false, version: None, id: None, timestamps: false, soft_delete: false }).unwrap();
+
+        let migrations: Vec<_> = fs::read_dir(root.join("migrations")).unwrap().flatten().collect();
+        assert_eq!(migrations.len(), 1);
+        assert!(migrations[0].file_name().to_string_lossy().ends_with("_create_heros.sql"));
+
+        let main_rs = fs::read_to_string(root.join("src/main.rs")).unwrap();
+        assert!(main_rs.contains(".nest(\"/hero\", hero::routes())"));
+        assert!(main_rs.contains(".nest(\"/hero\", hero::HeroApi::openapi())"));
+
+        let mod_rs = fs::read_to_string(root.join("src/hero/mod.rs")).unwrap();
+        assert!(mod_rs.contains("pub struct HeroApi;"));
+        let service = fs::read_to_string(root.join("src/hero/hero_service.rs")).unwrap();
+        assert!(service.contains("pub async fn create(dto: CreateHeroDto) -> String"));
+    });
+}
 
-    generate_entity("my".into()).unwrap();
+/// Build the generated project in `dir` and start it: the config loads on startup, so a bad one makes it exit.
+fn assert_generated_app_starts(dir: &Path) {
+    let target = Path::new(env!("CARGO_TARGET_TMPDIR")).join("generated");
+    let built = Command::new("cargo").arg("build").current_dir(dir).env("CARGO_TARGET_DIR", &target).status().unwrap();
+    assert!(built.success(), "the generated project does not compile");
+
+    let mut app = Command::new(target.join("debug/app"))
+        .current_dir(dir)
+        .env("APP_PORT", "0")
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    thread::sleep(Duration::from_secs(2));
+    if app.try_wait().unwrap().is_some() {
+        let mut stderr = String::new();
+        app.stderr.take().unwrap().read_to_string(&mut stderr).unwrap();
+        panic!("the generated app exited on startup:\n{}", stderr);
+    }
+    app.kill().unwrap();
+    app.wait().unwrap();
+}
 
-    assert!(file_path.exists());
+#[test]
+#[ignore = "builds and runs a generated project; run with `cargo test -- --ignored`"]
+fn integration_generated_config_keys_load() {
+    in_temp_project("config-loads", |root| {
+        create_new_project(NewOptions { name: "app".into(), project: ProjectConfig::default(), latest: false, files: vec![] }).unwrap();
+        env::set_current_dir(root.join("app")).unwrap();
+
+        generate_config_key("tags:Vec<String>".parse().unwrap(), None).unwrap();
+        generate_config_key("hosts:Vec<String>".parse().unwrap(), Some("[\"a\"]".into())).unwrap();
+        generate_config_key("ratio:f64".parse().unwrap(), Some("0.5".into())).unwrap();
+
+        assert_generated_app_starts(&root.join("app"));
+    });
+}
 
-    // cleanup
-    let _ = fs::remove_file(file_path_str);
+#[test]
+#[ignore = "builds and runs a generated project; run with `cargo test -- --ignored`"]
+fn integration_generated_api_key_config_loads() {
+    in_temp_project("api-key-loads", |root| {
+        create_new_project(NewOptions { name: "app".into(), project: ProjectConfig::default(), latest: false, files: vec![] }).unwrap();
+        env::set_current_dir(root.join("app")).unwrap();
+
+        let options = MiddlewareOptions {
+            name: "internal".into(),
+            kind: MiddlewareKind::ApiKey,
+            scope: MiddlewareScope::App,
+            order: LayerOrder::Outermost,
+            stateful: false,
+        };
+        generate_middleware(options).unwrap();
+
+        assert_generated_app_starts(&root.join("app"));
+    });
 }